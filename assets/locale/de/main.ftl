@@ -0,0 +1,87 @@
+game-title = Laughing Barnacle
+
+# Numbers
+decimal-separator = ,
+
+# Main menu
+menu-new-game = Neues Spiel
+menu-credits = Mitwirkende
+menu-settings = Optionen
+menu-quit = Beenden
+menu-back = Zurück
+menu-controls = Steuerung
+menu-reset = Standard
+menu-camera-keys = Kameratasten
+
+# Credits
+credits-title = Mitwirkende
+
+# Pause menu
+pause-title = Pause
+overrun-title = Überrannt!
+overrun-hint = Die Seepocken haben den Wal übernommen.
+menu-resume = Weiter
+menu-restart = Neustart
+menu-main-menu = Hauptmenü
+
+# Settings menu
+settings-master-volume = Gesamtlautstärke
+settings-sfx-volume = Effekte
+settings-music-volume = Musik
+settings-mouse-sensitivity = Mausempfindlichkeit
+settings-keyboard-camera-speed = Tempo der Kameratasten
+settings-invert-y = Y invertieren
+settings-camera-smoothing = Kameraglättung
+settings-orbit-inertia = Nachdrehen
+settings-rear-view = Rückansicht
+settings-rear-view-size = Größe der Rückansicht
+settings-anti-aliasing = Kantenglättung
+settings-shadows = Schatten
+settings-display = Anzeige
+settings-difficulty = Schwierigkeit
+settings-language = Sprache
+setting-on = An
+setting-off = Aus
+setting-windowed = Fenster
+setting-fullscreen = Voll
+difficulty-easy = Leicht
+difficulty-normal = Normal
+difficulty-hard = Schwer
+
+# Controls menu
+action-orbit = Drehen
+action-pan = Schwenken
+action-zoom = Zoomen
+action-remove = Entfernen
+action-focus-barnacle = Nächste Seepocke
+action-reset-view = Gesamtansicht
+action-pause = Pause
+action-quit = Beenden
+action-camera-left = Kamera links
+action-camera-right = Kamera rechts
+action-camera-up = Kamera hoch
+action-camera-down = Kamera runter
+action-zoom-in = Hineinzoomen
+action-zoom-out = Herauszoomen
+action-rear-view = Rückansicht
+action-viewpoint = Kameraansichten
+binding-mouse = Maus { $button }
+binding-wheel = Mausrad
+controls-press = Taste...
+controls-conflict = { $binding } ist schon { $action } zugewiesen
+controls-camera-keys-hint = Mit gedrückter Umschalttaste schwenken statt drehen. Pfeiltasten und Bild auf/ab gehen auch.
+
+# HUD
+hud-timer = { $time }
+hud-best-delta = Bestzeit { $delta }
+hud-barnacles = { $count }
+hud-overrun = Überrannt in { $seconds } s
+hud-overrun-steady = Stabil
+toast-barnacle-attached =
+    { $count ->
+        [one] Seepocke festgesetzt!
+       *[other] { $count } Seepocken festgesetzt!
+    }
+toast-overrun-warning = Die Seepocken breiten sich aus
+toast-overrun-danger = Der Wal ist fast überrannt!
+toast-wave = Welle { $wave }! { $time }
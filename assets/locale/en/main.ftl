@@ -0,0 +1,87 @@
+game-title = Laughing Barnacle
+
+# Numbers
+decimal-separator = .
+
+# Main menu
+menu-new-game = New Game
+menu-credits = Credits
+menu-settings = Settings
+menu-quit = Quit
+menu-back = Back
+menu-controls = Controls
+menu-reset = Reset
+menu-camera-keys = Camera keys
+
+# Credits
+credits-title = Credits
+
+# Pause menu
+pause-title = Paused
+overrun-title = Overrun!
+overrun-hint = The barnacles have taken over the whale.
+menu-resume = Resume
+menu-restart = Restart
+menu-main-menu = Main Menu
+
+# Settings menu
+settings-master-volume = Master volume
+settings-sfx-volume = SFX volume
+settings-music-volume = Music volume
+settings-mouse-sensitivity = Mouse sensitivity
+settings-keyboard-camera-speed = Camera key speed
+settings-invert-y = Invert Y
+settings-camera-smoothing = Camera smoothing
+settings-orbit-inertia = Orbit inertia
+settings-rear-view = Rear view
+settings-rear-view-size = Rear view size
+settings-anti-aliasing = Anti-aliasing
+settings-shadows = Shadows
+settings-display = Display
+settings-difficulty = Difficulty
+settings-language = Language
+setting-on = On
+setting-off = Off
+setting-windowed = Window
+setting-fullscreen = Full
+difficulty-easy = Easy
+difficulty-normal = Normal
+difficulty-hard = Hard
+
+# Controls menu
+action-orbit = Orbit
+action-pan = Pan
+action-zoom = Zoom
+action-remove = Remove
+action-focus-barnacle = Next barnacle
+action-reset-view = Reset view
+action-pause = Pause
+action-quit = Quit
+action-camera-left = Camera left
+action-camera-right = Camera right
+action-camera-up = Camera up
+action-camera-down = Camera down
+action-zoom-in = Zoom in
+action-zoom-out = Zoom out
+action-rear-view = Rear view
+action-viewpoint = Camera views
+binding-mouse = Mouse { $button }
+binding-wheel = Wheel
+controls-press = Press...
+controls-conflict = { $binding } is already bound to { $action }
+controls-camera-keys-hint = Hold Shift to pan instead of orbiting. Arrows and Page Up/Down also work.
+
+# HUD
+hud-timer = { $time }
+hud-best-delta = PB { $delta }
+hud-barnacles = { $count }
+hud-overrun = Overrun in { $seconds }s
+hud-overrun-steady = Holding steady
+toast-barnacle-attached =
+    { $count ->
+        [one] Barnacle attached!
+       *[other] { $count } barnacles attached!
+    }
+toast-overrun-warning = Barnacles are spreading
+toast-overrun-danger = The whale is nearly overrun!
+toast-wave = Wave { $wave }! { $time }
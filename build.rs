@@ -0,0 +1,72 @@
+// Lists asset files the game needs to know about at compile time, since the browser can't list
+// the assets folder at runtime
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let assets = Path::new(&manifest_dir).join("assets");
+    println!("cargo:rerun-if-changed=assets");
+
+    let menus = asset_files(&assets, "menus", &|name| name.ends_with(".menu.ron"));
+    fs::write(
+        Path::new(&out_dir).join("menu_files.rs"),
+        format!(
+            "/// Every `*.menu.ron` in `assets/menus`, listed by `build.rs` for the web build\n\
+             pub const MENU_FILES: &[&str] = &{:?};\n",
+            menus
+        ),
+    )
+    .unwrap();
+
+    // the same files the native build finds with `credits::is_credit_file`
+    let credits = asset_files(&assets, "", &|name| {
+        let lowercase = name.to_lowercase();
+        name.ends_with(".ATTRIBUTION")
+            || lowercase.starts_with("license")
+            || lowercase.starts_with("licence")
+    });
+    let entries: String = credits
+        .iter()
+        .map(|file| {
+            format!(
+                "    ({:?}, include_str!({:?})),\n",
+                file,
+                assets.join(file).to_string_lossy()
+            )
+        })
+        .collect();
+    fs::write(
+        Path::new(&out_dir).join("credit_files.rs"),
+        format!(
+            "/// Path in the assets folder and contents of every credit file, listed by `build.rs`\n\
+             const CREDIT_FILES: &[(&str, &str)] = &[\n{}];\n",
+            entries
+        ),
+    )
+    .unwrap();
+}
+
+// Paths relative to `assets` of the files in `folder` and its subfolders whose name passes
+// `filter`, sorted so the build output is stable
+fn asset_files(assets: &Path, folder: &str, filter: &dyn Fn(&str) -> bool) -> Vec<String> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(assets.join(folder)).unwrap() {
+        let entry = entry.unwrap();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = match folder {
+            "" => name.clone(),
+            folder => format!("{}/{}", folder, name),
+        };
+        if entry.file_type().unwrap().is_dir() {
+            files.extend(asset_files(assets, &path, filter));
+        } else if filter(&name) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
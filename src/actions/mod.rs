@@ -0,0 +1,406 @@
+use super::settings::{Settings, VIEWPOINT_SLOTS};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Everything the player can do in game. Systems read these from the `Input<Action>` resource
+/// instead of looking at raw keys and buttons, so the controls can be rebound.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Orbit,
+    Pan,
+    Zoom,
+    Remove,
+    /// Frames the next attaching barnacle, the one closest to attaching first
+    FocusBarnacle,
+    /// Returns the camera to the view of the whole whale
+    ResetView,
+    Pause,
+    Quit,
+    /// Keyboard camera: orbit while held, or pan together with Shift
+    CameraLeft,
+    CameraRight,
+    CameraUp,
+    CameraDown,
+    ZoomIn,
+    ZoomOut,
+    /// Shows or hides the view of the far side of the whale
+    RearView,
+    /// Stores the camera view with Ctrl and a number key, and flies back to it with the number
+    /// key alone. Its keys are fixed, so it is not in `ALL`.
+    Viewpoint,
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::Orbit,
+        Action::Pan,
+        Action::Zoom,
+        Action::Remove,
+        Action::FocusBarnacle,
+        Action::ResetView,
+        Action::Pause,
+        Action::Quit,
+        Action::CameraLeft,
+        Action::CameraRight,
+        Action::CameraUp,
+        Action::CameraDown,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::RearView,
+    ];
+}
+
+/// A physical input an action can be bound to
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Only meaningful for `Action::Zoom`, which reads the scroll amount itself
+    MouseWheel,
+}
+
+/// Which binding triggers each action. Persisted as part of the `Settings`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionMap {
+    #[serde(deserialize_with = "with_default_bindings")]
+    bindings: BTreeMap<Action, Binding>,
+}
+
+// Files saved before an action existed don't bind it, so it gets its default binding, unless
+// the player already uses that for something else
+fn with_default_bindings<'de, D>(deserializer: D) -> Result<BTreeMap<Action, Binding>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut bindings = BTreeMap::<Action, Binding>::deserialize(deserializer)?;
+    for (action, binding) in ActionMap::default().bindings {
+        if !bindings.contains_key(&action) && !bindings.values().any(|b| *b == binding) {
+            bindings.insert(action, binding);
+        }
+    }
+    Ok(bindings)
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let bindings = [
+            (Action::Orbit, Binding::Mouse(MouseButton::Right)),
+            (Action::Pan, Binding::Mouse(MouseButton::Middle)),
+            (Action::Zoom, Binding::MouseWheel),
+            (Action::Remove, Binding::Mouse(MouseButton::Left)),
+            (Action::FocusBarnacle, Binding::Key(KeyCode::Tab)),
+            (Action::ResetView, Binding::Key(KeyCode::R)),
+            (Action::Pause, Binding::Key(KeyCode::Escape)),
+            (Action::Quit, Binding::Key(KeyCode::Q)),
+            (Action::CameraLeft, Binding::Key(KeyCode::A)),
+            (Action::CameraRight, Binding::Key(KeyCode::D)),
+            (Action::CameraUp, Binding::Key(KeyCode::W)),
+            (Action::CameraDown, Binding::Key(KeyCode::S)),
+            (Action::ZoomIn, Binding::Key(KeyCode::Equals)),
+            (Action::ZoomOut, Binding::Key(KeyCode::Minus)),
+            (Action::RearView, Binding::Key(KeyCode::V)),
+        ];
+        ActionMap {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl ActionMap {
+    pub fn binding(&self, action: Action) -> Option<Binding> {
+        self.bindings.get(&action).copied()
+    }
+
+    /// Binds `action` to `binding`, unless another action already uses it, either as its
+    /// binding or as one of the fixed `KEY_BINDINGS` and viewpoint keys, in which case that
+    /// action is returned and nothing changes.
+    pub fn rebind(&mut self, action: Action, binding: Binding) -> Result<(), Action> {
+        if let Some(other) = self.action_for(binding) {
+            if other != action {
+                return Err(other);
+            }
+        }
+        self.bindings.insert(action, binding);
+        Ok(())
+    }
+
+    fn action_for(&self, binding: Binding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, b)| **b == binding)
+            .map(|(action, _)| *action)
+            .or_else(|| fixed_action_for(binding))
+    }
+
+    /// Pairs of actions sharing a binding, which a hand edited settings file can contain
+    pub fn conflicts(&self) -> Vec<(Action, Action)> {
+        let mut conflicts = Vec::new();
+        for (a, binding_a) in self.bindings.iter() {
+            for (b, binding_b) in self.bindings.range(*a..).skip(1) {
+                if binding_a == binding_b {
+                    conflicts.push((*a, *b));
+                }
+            }
+            if let Some(fixed) = fixed_action_for(*binding_a).filter(|fixed| fixed != a) {
+                conflicts.push((*a, fixed));
+            }
+        }
+        conflicts
+    }
+}
+
+// Fixed gamepad layout. The sticks and triggers drive the camera and cursor directly.
+const GAMEPAD_BINDINGS: [(Action, GamepadButtonType); 6] = [
+    (Action::Pan, GamepadButtonType::LeftTrigger),
+    (Action::Remove, GamepadButtonType::South),
+    (Action::FocusBarnacle, GamepadButtonType::North),
+    (Action::ResetView, GamepadButtonType::RightThumb),
+    (Action::Pause, GamepadButtonType::Start),
+    (Action::Quit, GamepadButtonType::Select),
+];
+
+// Fixed alternatives to the rebindable keyboard camera keys
+const KEY_BINDINGS: [(Action, KeyCode); 9] = [
+    (Action::CameraLeft, KeyCode::Left),
+    (Action::CameraRight, KeyCode::Right),
+    (Action::CameraUp, KeyCode::Up),
+    (Action::CameraDown, KeyCode::Down),
+    (Action::ZoomIn, KeyCode::PageUp),
+    (Action::ZoomIn, KeyCode::Plus),
+    (Action::ZoomIn, KeyCode::NumpadAdd),
+    (Action::ZoomOut, KeyCode::PageDown),
+    (Action::ZoomOut, KeyCode::NumpadSubtract),
+];
+
+/// Number keys of the camera view slots
+pub const VIEWPOINT_KEYS: [KeyCode; VIEWPOINT_SLOTS] =
+    [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+
+/// Held together with a viewpoint key to store the view instead of recalling it
+pub const VIEWPOINT_STORE_KEYS: [KeyCode; 2] = [KeyCode::LControl, KeyCode::RControl];
+
+fn fixed_action_for(binding: Binding) -> Option<Action> {
+    KEY_BINDINGS
+        .iter()
+        .find(|(_, key)| binding == Binding::Key(*key))
+        .map(|(action, _)| *action)
+        .or_else(|| {
+            VIEWPOINT_KEYS
+                .iter()
+                .chain(VIEWPOINT_STORE_KEYS.iter())
+                .any(|key| binding == Binding::Key(*key))
+                .then_some(Action::Viewpoint)
+        })
+}
+
+/// The gamepad playing the game: the first one connected, replaced by another
+/// connected one when it is unplugged.
+#[derive(Default)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Input<Action>>()
+            .init_resource::<ActiveGamepad>()
+            .add_startup_system(report_conflicts)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gamepad_connections
+                    .label("gamepad_connections")
+                    .after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_actions
+                    .after(InputSystem)
+                    .after("gamepad_connections"),
+            );
+    }
+}
+
+fn gamepad_connections(
+    mut active: ResMut<ActiveGamepad>,
+    gamepads: Res<Gamepads>,
+    mut events: EventReader<GamepadEvent>,
+) {
+    for GamepadEvent(gamepad, event_type) in events.iter() {
+        match event_type {
+            GamepadEventType::Connected => {
+                info!("{:?} connected", gamepad);
+                if active.0.is_none() {
+                    active.0 = Some(*gamepad);
+                }
+            }
+            GamepadEventType::Disconnected => {
+                info!("{:?} disconnected", gamepad);
+                if active.0 == Some(*gamepad) {
+                    active.0 = gamepads.iter().find(|g| *g != gamepad).copied();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn report_conflicts(settings: Res<Settings>) {
+    for (a, b) in settings.bindings.conflicts() {
+        warn!("{:?} and {:?} share the same binding", a, b);
+    }
+}
+
+// Translates the raw keyboard, mouse and gamepad state into action presses and releases
+fn update_actions(
+    settings: Res<Settings>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepad: Res<ActiveGamepad>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut actions: ResMut<Input<Action>>,
+) {
+    actions.clear();
+    for action in Action::ALL {
+        let pressed = match settings.bindings.binding(action) {
+            Some(Binding::Key(key)) => keys.pressed(key),
+            Some(Binding::Mouse(button)) => mouse.pressed(button),
+            Some(Binding::MouseWheel) | None => false,
+        };
+        let key_pressed = KEY_BINDINGS
+            .iter()
+            .any(|(bound, key)| *bound == action && keys.pressed(*key));
+        let pad_pressed = gamepad.0.is_some_and(|gamepad| {
+            GAMEPAD_BINDINGS.iter().any(|(bound, button)| {
+                *bound == action && gamepad_buttons.pressed(GamepadButton(gamepad, *button))
+            })
+        });
+        if pressed || key_pressed || pad_pressed {
+            actions.press(action);
+        } else if actions.pressed(action) {
+            actions.release(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_do_not_conflict() {
+        let map = ActionMap::default();
+        assert!(map.conflicts().is_empty());
+        for action in Action::ALL {
+            assert!(map.binding(action).is_some(), "{:?} is unbound", action);
+        }
+    }
+
+    #[test]
+    fn rebind_to_a_free_binding() {
+        let mut map = ActionMap::default();
+        assert_eq!(map.rebind(Action::Quit, Binding::Key(KeyCode::F10)), Ok(()));
+        assert_eq!(map.binding(Action::Quit), Some(Binding::Key(KeyCode::F10)));
+        // the old binding is free again
+        assert_eq!(map.rebind(Action::Pause, Binding::Key(KeyCode::Q)), Ok(()));
+        // rebinding to the current binding changes nothing
+        assert_eq!(map.rebind(Action::Pause, Binding::Key(KeyCode::Q)), Ok(()));
+        assert!(map.conflicts().is_empty());
+    }
+
+    #[test]
+    fn rebind_refuses_a_binding_in_use() {
+        let mut map = ActionMap::default();
+        let before = map.clone();
+        assert_eq!(
+            map.rebind(Action::Quit, Binding::Key(KeyCode::R)),
+            Err(Action::ResetView)
+        );
+        assert_eq!(map, before);
+    }
+
+    #[test]
+    fn rebind_refuses_the_viewpoint_keys() {
+        let mut map = ActionMap::default();
+        for key in [KeyCode::Key3, KeyCode::RControl] {
+            assert_eq!(
+                map.rebind(Action::Quit, Binding::Key(key)),
+                Err(Action::Viewpoint)
+            );
+        }
+        let map: ActionMap = ron::from_str("(bindings: {Pause: Key(LControl)})").unwrap();
+        assert_eq!(map.conflicts(), vec![(Action::Pause, Action::Viewpoint)]);
+    }
+
+    #[test]
+    fn bindings_are_swapped_through_a_free_one() {
+        let mut map = ActionMap::default();
+        let orbit = map.binding(Action::Orbit).unwrap();
+        let pan = map.binding(Action::Pan).unwrap();
+        assert_eq!(map.rebind(Action::Orbit, pan), Err(Action::Pan));
+        assert_eq!(map.rebind(Action::Pan, Binding::Key(KeyCode::F1)), Ok(()));
+        assert_eq!(map.rebind(Action::Orbit, pan), Ok(()));
+        assert_eq!(map.rebind(Action::Pan, orbit), Ok(()));
+        assert_eq!(map.binding(Action::Orbit), Some(pan));
+        assert_eq!(map.binding(Action::Pan), Some(orbit));
+        assert!(map.conflicts().is_empty());
+    }
+
+    #[test]
+    fn conflicts_of_a_hand_edited_file_are_found() {
+        let map: ActionMap =
+            ron::from_str("(bindings: {Quit: Key(R), ResetView: Key(R), Remove: Key(Up)})")
+                .unwrap();
+        assert_eq!(
+            map.conflicts(),
+            vec![
+                (Action::Remove, Action::CameraUp),
+                (Action::ResetView, Action::Quit)
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_actions_get_their_default_binding() {
+        let map: ActionMap = ron::from_str("(bindings: {Quit: Key(F10)})").unwrap();
+        assert_eq!(map.binding(Action::Quit), Some(Binding::Key(KeyCode::F10)));
+        assert_eq!(
+            map.binding(Action::RearView),
+            ActionMap::default().binding(Action::RearView)
+        );
+        assert!(map.conflicts().is_empty());
+    }
+
+    #[test]
+    fn default_bindings_in_use_are_left_unbound() {
+        // a file from before the rear view existed, with its key taken by another action
+        let map: ActionMap = ron::from_str("(bindings: {Quit: Key(V)})").unwrap();
+        assert_eq!(map.binding(Action::Quit), Some(Binding::Key(KeyCode::V)));
+        assert_eq!(map.binding(Action::RearView), None);
+        assert!(map.conflicts().is_empty());
+    }
+
+    #[test]
+    fn fixed_keys_can_not_be_rebound() {
+        let mut map = ActionMap::default();
+        assert_eq!(
+            map.rebind(Action::Remove, Binding::Key(KeyCode::Left)),
+            Err(Action::CameraLeft)
+        );
+        assert_eq!(
+            map.rebind(Action::Pause, Binding::Key(KeyCode::NumpadAdd)),
+            Err(Action::ZoomIn)
+        );
+        assert_eq!(
+            map.binding(Action::Remove),
+            Some(Binding::Mouse(MouseButton::Left))
+        );
+        // an action may also be bound to its own fixed key
+        assert_eq!(
+            map.rebind(Action::ZoomOut, Binding::Key(KeyCode::PageDown)),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,276 @@
+// Launch options given on the command line
+
+use super::settings::Difficulty;
+use super::GameState;
+use std::fmt;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: bevyjam-laughing-barnacle [OPTIONS]
+
+Options:
+  --start <menu|game>       Screen shown on launch [default: menu]
+  --seed <number>           Seed of the barnacle spawns, random by default
+  --mode <easy|normal|hard> Difficulty of the run, overriding the settings
+  --config <file>           Read and save the settings in this file
+  --headless                Run without a window or renderer, implies --start game
+                            and quits once the whale is overrun or the replay ends
+  --replay <file>           Play back a recorded run, implies --start game
+  --window <WxH>            Window size in pixels, for example 1280x720
+  -h, --help                Print this help
+
+The last run is recorded to laughing-barnacle/replay.ron in the user's config
+directory, also when --config points the settings elsewhere.";
+
+/// Options of this launch. Everything not given keeps its default or saved value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub start: GameState,
+    pub seed: Option<u64>,
+    pub mode: Option<Difficulty>,
+    pub config: Option<PathBuf>,
+    pub headless: bool,
+    pub replay: Option<PathBuf>,
+    pub window: Option<(f32, f32)>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            start: GameState::Menu,
+            seed: None,
+            mode: None,
+            config: None,
+            headless: false,
+            replay: None,
+            window: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Options),
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    UnknownOption(String),
+    MissingValue(&'static str),
+    /// A value was given to a flag such as `--headless=yes`
+    UnexpectedValue(&'static str),
+    InvalidValue {
+        option: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::UnknownOption(option) => write!(f, "unknown option '{}'", option),
+            CliError::MissingValue(option) => write!(f, "'{}' needs a value", option),
+            CliError::UnexpectedValue(option) => write!(f, "'{}' takes no value", option),
+            CliError::InvalidValue {
+                option,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value '{}' for '{}', expected {}",
+                value, option, expected
+            ),
+        }
+    }
+}
+
+/// Parses the arguments, without the program name. Values follow their option either as the
+/// next argument or after an `=`.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, CliError> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let option = match OPTIONS.iter().find(|option| **option == name) {
+            Some(option) => *option,
+            None => return Err(CliError::UnknownOption(arg)),
+        };
+
+        match option {
+            "--help" | "-h" => return Ok(Command::Help),
+            "--headless" => {
+                if inline_value.is_some() {
+                    return Err(CliError::UnexpectedValue(option));
+                }
+                options.headless = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        let value = match inline_value {
+            Some(value) => value.to_string(),
+            None => args.next().ok_or(CliError::MissingValue(option))?,
+        };
+        let invalid = |expected| CliError::InvalidValue {
+            option,
+            value: value.clone(),
+            expected,
+        };
+        match option {
+            "--start" => {
+                options.start = match value.as_str() {
+                    "menu" => GameState::Menu,
+                    "game" => GameState::Game,
+                    _ => return Err(invalid("'menu' or 'game'")),
+                }
+            }
+            "--seed" => options.seed = Some(value.parse().map_err(|_| invalid("a number"))?),
+            "--mode" => {
+                options.mode = Some(match value.as_str() {
+                    "easy" => Difficulty::Easy,
+                    "normal" => Difficulty::Normal,
+                    "hard" => Difficulty::Hard,
+                    _ => return Err(invalid("'easy', 'normal' or 'hard'")),
+                })
+            }
+            "--config" => options.config = Some(PathBuf::from(&value)),
+            "--replay" => options.replay = Some(PathBuf::from(&value)),
+            "--window" => {
+                let size = value
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)))
+                    .filter(|(w, h)| *w > 0 && *h > 0);
+                match size {
+                    Some((w, h)) => options.window = Some((w as f32, h as f32)),
+                    None => return Err(invalid("a size like 1280x720")),
+                }
+            }
+            _ => unreachable!("option without a parser: {}", option),
+        }
+    }
+
+    // nobody could leave the menu of a headless run
+    if options.replay.is_some() || options.headless {
+        options.start = GameState::Game;
+    }
+    Ok(Command::Run(options))
+}
+
+const OPTIONS: [&str; 9] = [
+    "--start",
+    "--seed",
+    "--mode",
+    "--config",
+    "--headless",
+    "--replay",
+    "--window",
+    "--help",
+    "-h",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<Command, CliError> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match run(args) {
+            Ok(Command::Run(options)) => options,
+            other => panic!("{:?} did not parse to options: {:?}", args, other),
+        }
+    }
+
+    #[test]
+    fn no_arguments_keep_the_defaults() {
+        assert_eq!(options(&[]), Options::default());
+    }
+
+    #[test]
+    fn every_option_is_parsed() {
+        assert_eq!(options(&["--start", "game"]).start, GameState::Game);
+        assert_eq!(options(&["--start=menu"]).start, GameState::Menu);
+        assert_eq!(options(&["--seed", "42"]).seed, Some(42));
+        assert_eq!(options(&["--mode=hard"]).mode, Some(Difficulty::Hard));
+        assert_eq!(
+            options(&["--config", "my.ron"]).config,
+            Some(PathBuf::from("my.ron"))
+        );
+        assert!(options(&["--headless"]).headless);
+        assert_eq!(
+            options(&["--window", "1280x720"]).window,
+            Some((1280.0, 720.0))
+        );
+        assert_eq!(run(&["--seed", "1", "--help"]), Ok(Command::Help));
+        assert_eq!(run(&["-h"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn replay_starts_the_game() {
+        let options = options(&["--start", "menu", "--replay", "run.ron"]);
+        assert_eq!(options.replay, Some(PathBuf::from("run.ron")));
+        assert_eq!(options.start, GameState::Game);
+    }
+
+    #[test]
+    fn headless_starts_the_game() {
+        let options = options(&["--start", "menu", "--headless"]);
+        assert!(options.headless);
+        assert_eq!(options.start, GameState::Game);
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        assert_eq!(
+            run(&["--fullscreen"]),
+            Err(CliError::UnknownOption("--fullscreen".to_string()))
+        );
+        assert_eq!(
+            run(&["game"]),
+            Err(CliError::UnknownOption("game".to_string()))
+        );
+    }
+
+    #[test]
+    fn missing_values_are_rejected() {
+        assert_eq!(run(&["--seed"]), Err(CliError::MissingValue("--seed")));
+        assert_eq!(
+            run(&["--headless", "--window"]),
+            Err(CliError::MissingValue("--window"))
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        for args in [
+            &["--start", "paused"][..],
+            &["--seed", "-1"],
+            &["--mode", "insane"],
+            &["--window", "1280"],
+            &["--window", "0x720"],
+        ] {
+            assert!(
+                matches!(run(args), Err(CliError::InvalidValue { .. })),
+                "{:?} was accepted",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn flags_take_no_value() {
+        assert_eq!(
+            run(&["--headless=yes"]),
+            Err(CliError::UnexpectedValue("--headless"))
+        );
+    }
+}
@@ -1,17 +1,26 @@
-use super::OnGameScreen;
+use super::super::actions::{Action, ActiveGamepad, Binding};
+use super::super::settings::{Settings, Viewpoint};
+use super::touch::TouchGestures;
+use super::{GameCamera, OnGameScreen, Whale};
 use bevy::input::mouse::{MouseMotion, MouseWheel};
 use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
 use bevy_mod_picking::PickingCameraBundle;
+use bevy_mod_raycast::{ray_intersection_over_mesh, Ray3d};
+use std::f32::consts::{FRAC_PI_2, PI};
 
 //adapted from https://bevy-cheatbook.github.io/cookbook/pan-orbit-camera.html
 
 /// Tags an entity as capable of panning and orbiting.
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
 pub struct PanOrbitCamera {
     /// The "focus point" to orbit around. It is automatically updated when panning the camera
     pub focus: Vec3,
     pub radius: f32,
-    pub upside_down: bool,
+    /// Angle around the vertical axis in radians, 0 looks from +Z towards -Z
+    pub yaw: f32,
+    /// Angle above the horizontal plane in radians, positive looks down at the focus
+    pub pitch: f32,
 }
 
 impl Default for PanOrbitCamera {
@@ -19,121 +28,688 @@ impl Default for PanOrbitCamera {
         PanOrbitCamera {
             focus: Vec3::ZERO,
             radius: 5.0,
-            upside_down: false,
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+}
+
+impl PanOrbitCamera {
+    /// Camera at `eye` orbiting around `focus`
+    pub fn looking_at(focus: Vec3, eye: Vec3) -> Self {
+        let offset = eye - focus;
+        let radius = offset.length();
+        PanOrbitCamera {
+            focus,
+            radius,
+            yaw: offset.x.atan2(offset.z),
+            pitch: (offset.y / radius).clamp(-1.0, 1.0).asin(),
+        }
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(-self.pitch)
+    }
+
+    pub fn eye(&self) -> Vec3 {
+        self.focus + self.rotation() * Vec3::new(0.0, 0.0, self.radius)
+    }
+
+    /// The camera the fraction `t` of the way to `target`
+    pub fn approach(&self, target: &PanOrbitCamera, t: f32) -> Self {
+        if t >= 1.0 {
+            return *target;
+        }
+        PanOrbitCamera {
+            focus: self.focus.lerp(target.focus, t),
+            // zooming in log space moves at the same relative pace close up and far away
+            radius: self.radius * (target.radius / self.radius).powf(t),
+            yaw: self.yaw + (target.yaw - self.yaw) * t,
+            pitch: self.pitch + (target.pitch - self.pitch) * t,
+        }
+    }
+
+    pub fn viewpoint(&self) -> Viewpoint {
+        Viewpoint {
+            focus: self.focus,
+            radius: self.radius,
+            yaw: self.yaw,
+            pitch: self.pitch,
+        }
+    }
+
+    pub fn from_viewpoint(viewpoint: &Viewpoint) -> Self {
+        PanOrbitCamera {
+            focus: viewpoint.focus,
+            radius: viewpoint.radius,
+            yaw: viewpoint.yaw,
+            pitch: viewpoint.pitch,
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform {
+            translation: self.eye(),
+            rotation: self.rotation(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Where the camera is while it catches up with its `PanOrbitCamera`, which input and other
+/// systems move instantly
+#[derive(Component)]
+pub struct SmoothedCamera {
+    pub current: PanOrbitCamera,
+    /// Yaw and pitch speed in radians per second, kept after releasing the orbit button
+    pub orbit_velocity: Vec2,
+}
+
+impl SmoothedCamera {
+    pub fn new(current: PanOrbitCamera) -> Self {
+        SmoothedCamera {
+            current,
+            orbit_velocity: Vec2::ZERO,
+        }
+    }
+}
+
+// Seconds a camera transition takes
+const TRANSITION_SECONDS: f32 = 0.6;
+
+/// Moves the `PanOrbitCamera` to another view over a short time, easing in and out. Moving the
+/// camera by hand ends the transition where it is.
+#[derive(Component)]
+pub struct CameraTransition {
+    from: PanOrbitCamera,
+    to: PanOrbitCamera,
+    elapsed: f32,
+    // what the transition set last, anything else means someone else moved the camera
+    last: PanOrbitCamera,
+}
+
+impl CameraTransition {
+    pub fn new(from: PanOrbitCamera, mut to: PanOrbitCamera) -> Self {
+        // turn the short way around
+        to.yaw = from.yaw + wrap_angle(to.yaw - from.yaw);
+        CameraTransition {
+            from,
+            to,
+            elapsed: 0.0,
+            last: from,
+        }
+    }
+
+    /// The camera `elapsed` seconds into the transition
+    pub fn at(&self, elapsed: f32) -> PanOrbitCamera {
+        let t = (elapsed / TRANSITION_SECONDS).clamp(0.0, 1.0);
+        let eased = t * t * (3.0 - 2.0 * t);
+        self.from.approach(&self.to, eased)
+    }
+}
+
+// The same angle within -π..π
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+pub fn animate_camera_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut PanOrbitCamera, &mut CameraTransition)>,
+) {
+    for (entity, mut pan_orbit, mut transition) in query.iter_mut() {
+        if *pan_orbit != transition.last {
+            commands.entity(entity).remove::<CameraTransition>();
+            continue;
+        }
+        transition.elapsed += time.delta_seconds();
+        *pan_orbit = transition.at(transition.elapsed);
+        transition.last = *pan_orbit;
+        if transition.elapsed >= TRANSITION_SECONDS {
+            commands.entity(entity).remove::<CameraTransition>();
+        }
+    }
+}
+
+/// The view of the whole whale the game starts with
+pub fn default_view() -> PanOrbitCamera {
+    PanOrbitCamera::looking_at(Vec3::ZERO, Vec3::new(-2.0, 2.5, 5.0))
+}
+
+/// Fraction of the remaining distance covered in `dt` seconds when closing in exponentially
+/// with the time constant `smoothing`
+pub fn damping(smoothing: f32, dt: f32) -> f32 {
+    if smoothing <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-dt / smoothing).exp()
+    }
+}
+
+// Time constants of the orbit speed, when measuring it from the mouse and when it dies down
+// after release, and the speed in radians per second below which it stops
+const VELOCITY_SMOOTHING: f32 = 0.05;
+const INERTIA_DECAY: f32 = 0.3;
+const MIN_INERTIA_SPEED: f32 = 0.01;
+
+// Limits derived from the whale's bounding box: the camera stays at least the largest half
+// extent away from the focus and within a few whale sizes, and the focus stays in a box
+// half again as large as the whale's
+const MAX_RADIUS_FACTOR: f32 = 4.0;
+const FOCUS_MARGIN_FACTOR: f32 = 1.5;
+const MAX_PITCH: f32 = 80.0 * PI / 180.0;
+
+/// Bounds of the `PanOrbitCamera` keeping the whale in view. Replaced by limits around the whale
+/// once its mesh is loaded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraLimits {
+    /// Lowest and highest pitch, in radians. Must stay within ±90° to keep the camera upright.
+    pub pitch: (f32, f32),
+    /// Smallest and largest distance to the focus
+    pub radius: (f32, f32),
+    /// Corners of the box the focus stays in
+    pub focus_min: Vec3,
+    pub focus_max: Vec3,
+}
+
+impl Default for CameraLimits {
+    fn default() -> Self {
+        CameraLimits {
+            pitch: (-MAX_PITCH, MAX_PITCH),
+            // dont allow zoom to reach zero or you get stuck
+            radius: (0.05, f32::MAX),
+            focus_min: Vec3::splat(f32::MIN),
+            focus_max: Vec3::splat(f32::MAX),
         }
     }
 }
 
-/// Pan the camera with middle mouse click, zoom with scroll wheel, orbit with right mouse click.
+impl CameraLimits {
+    /// Limits for a whale with the given world space bounding box
+    pub fn around(center: Vec3, half_extents: Vec3) -> Self {
+        let margin = half_extents * FOCUS_MARGIN_FACTOR;
+        CameraLimits {
+            radius: (
+                half_extents.max_element(),
+                half_extents.length() * MAX_RADIUS_FACTOR,
+            ),
+            focus_min: center - margin,
+            focus_max: center + margin,
+            ..Default::default()
+        }
+    }
+
+    /// The configured pitch range within ±90°
+    pub fn pitch_range(&self) -> (f32, f32) {
+        (self.pitch.0.max(-FRAC_PI_2), self.pitch.1.min(FRAC_PI_2))
+    }
+
+    pub fn clamp(&self, camera: &mut PanOrbitCamera) {
+        let (min_pitch, max_pitch) = self.pitch_range();
+        camera.pitch = camera.pitch.clamp(min_pitch, max_pitch);
+        camera.radius = camera.radius.clamp(self.radius.0, self.radius.1);
+        camera.focus = camera.focus.clamp(self.focus_min, self.focus_max);
+    }
+}
+
+pub fn reset_camera_limits(mut limits: ResMut<CameraLimits>) {
+    *limits = CameraLimits::default();
+}
+
+// Takes the limits from the whale's bounding box once the mesh is loaded and its bounds known
+#[allow(clippy::type_complexity)]
+pub fn update_camera_limits(
+    mut limits: ResMut<CameraLimits>,
+    whale_query: Query<(&Aabb, &GlobalTransform), (With<Whale>, Changed<Aabb>)>,
+) {
+    for (aabb, transform) in whale_query.iter() {
+        *limits = CameraLimits::around(
+            transform.mul_vec3(aabb.center),
+            aabb.half_extents * transform.scale,
+        );
+    }
+}
+
+// Mouse travel in pixels that zooms as much as one step of the scroll wheel
+const ZOOM_DRAG_PIXELS: f32 = 100.0;
+// Gamepad speeds at full stick or trigger deflection, in mouse pixels and scroll steps per second
+const STICK_ORBIT_SPEED: f32 = 800.0;
+const STICK_PAN_SPEED: f32 = 600.0;
+const TRIGGER_ZOOM_SPEED: f32 = 4.0;
+// Fraction of the radius one scroll step zooms
+const ZOOM_STEP: f32 = 0.2;
+// Keyboard camera speeds before the speed setting: radians, focus distances and scroll steps
+// per second
+const KEY_ORBIT_SPEED: f32 = 1.5;
+const KEY_PAN_SPEED: f32 = 0.6;
+const KEY_ZOOM_SPEED: f32 = 4.0;
+
+/// Pan, orbit and zoom the camera with whatever the `Pan`, `Orbit` and `Zoom` actions are bound to.
+/// By default that is middle mouse click, right mouse click and the scroll wheel.
+/// On a gamepad the right stick orbits, or pans while the left bumper is held, and the triggers zoom.
+/// The keyboard camera keys orbit, or pan while Shift is held, and zoom.
+/// On a touch screen one finger orbits, two fingers pan and pinching zooms.
+#[allow(clippy::too_many_arguments)]
 pub fn pan_orbit_camera(
     windows: Res<Windows>,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
     mut ev_motion: EventReader<MouseMotion>,
     mut ev_scroll: EventReader<MouseWheel>,
-    input_mouse: Res<Input<MouseButton>>,
-    mut query: Query<(&mut PanOrbitCamera, &mut Transform, &PerspectiveProjection)>,
+    actions: Res<Input<Action>>,
+    settings: Res<Settings>,
+    gamepad: Res<ActiveGamepad>,
+    axes: Res<Axis<GamepadAxis>>,
+    button_axes: Res<Axis<GamepadButton>>,
+    limits: Res<CameraLimits>,
+    touch: Res<TouchGestures>,
+    mut query: Query<(
+        &mut PanOrbitCamera,
+        &mut SmoothedCamera,
+        &PerspectiveProjection,
+    )>,
 ) {
-    // change input mapping for orbit and panning here
-    let orbit_button = MouseButton::Right;
-    let pan_button = MouseButton::Middle;
+    // headless runs have no window to measure the input against
+    let window = match windows.get_primary() {
+        Some(window) => Vec2::new(window.width(), window.height()),
+        None => return,
+    };
+    let zoom_with_wheel = settings.bindings.binding(Action::Zoom) == Some(Binding::MouseWheel);
 
     let mut pan = Vec2::ZERO;
     let mut rotation_move = Vec2::ZERO;
     let mut scroll = 0.0;
-    let mut orbit_button_changed = false;
 
-    if input_mouse.pressed(orbit_button) {
+    if actions.pressed(Action::Orbit) {
         for ev in ev_motion.iter() {
             rotation_move += ev.delta;
         }
-    } else if input_mouse.pressed(pan_button) {
+        rotation_move *= settings.mouse_sensitivity;
+        if settings.invert_y {
+            rotation_move.y = -rotation_move.y;
+        }
+    } else if actions.pressed(Action::Pan) {
         // Pan only if we're not rotating at the moment
         for ev in ev_motion.iter() {
             pan += ev.delta;
         }
+    } else if !zoom_with_wheel && actions.pressed(Action::Zoom) {
+        // zoom bound to a button: dragging up zooms in
+        for ev in ev_motion.iter() {
+            scroll -= ev.delta.y / ZOOM_DRAG_PIXELS;
+        }
     }
     for ev in ev_scroll.iter() {
-        scroll += ev.y;
+        if zoom_with_wheel {
+            scroll += ev.y;
+        }
     }
-    if input_mouse.just_released(orbit_button) || input_mouse.just_pressed(orbit_button) {
-        orbit_button_changed = true;
+    if let Some(gamepad) = gamepad.0 {
+        let stick = Vec2::new(
+            axes.get(GamepadAxis(gamepad, GamepadAxisType::RightStickX))
+                .unwrap_or(0.0),
+            -axes
+                .get(GamepadAxis(gamepad, GamepadAxisType::RightStickY))
+                .unwrap_or(0.0),
+        ) * time.delta_seconds();
+        if actions.pressed(Action::Pan) {
+            pan += stick * STICK_PAN_SPEED;
+        } else {
+            let mut stick_orbit = stick * STICK_ORBIT_SPEED;
+            if settings.invert_y {
+                stick_orbit.y = -stick_orbit.y;
+            }
+            rotation_move += stick_orbit;
+        }
+        let trigger = |button| {
+            button_axes
+                .get(GamepadButton(gamepad, button))
+                .unwrap_or(0.0)
+        };
+        scroll += (trigger(GamepadButtonType::RightTrigger2)
+            - trigger(GamepadButtonType::LeftTrigger2))
+            * TRIGGER_ZOOM_SPEED
+            * time.delta_seconds();
     }
 
-    for (mut pan_orbit, mut transform, projection) in query.iter_mut() {
-        if orbit_button_changed {
-            // only check for upside down when orbiting started or ended this frame
-            // if the camera is "upside" down, panning horizontally would be inverted, so invert the input to make it correct
-            let up = transform.rotation * Vec3::Y;
-            pan_orbit.upside_down = up.y <= 0.0;
-        }
+    rotation_move += touch.orbit;
+    pan += touch.pan;
+    if touch.pinch != 1.0 {
+        // spreading the fingers to twice the distance halves the radius
+        scroll += (1.0 - 1.0 / touch.pinch) / ZOOM_STEP;
+    }
+
+    let dt = time.delta_seconds();
+    let key_axis = |negative, positive| {
+        let pressed = |action| if actions.pressed(action) { 1.0 } else { 0.0 };
+        pressed(positive) - pressed(negative)
+    };
+    let key_move = Vec2::new(
+        key_axis(Action::CameraLeft, Action::CameraRight),
+        key_axis(Action::CameraDown, Action::CameraUp),
+    ) * settings.keyboard_camera_speed
+        * dt;
+    let (key_orbit, key_pan) = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        (Vec2::ZERO, key_move * KEY_PAN_SPEED)
+    } else {
+        (key_move * KEY_ORBIT_SPEED, Vec2::ZERO)
+    };
+    scroll += key_axis(Action::ZoomOut, Action::ZoomIn)
+        * KEY_ZOOM_SPEED
+        * settings.keyboard_camera_speed
+        * dt;
 
-        let mut any = false;
+    for (mut pan_orbit, mut smoothed, projection) in query.iter_mut() {
+        let mut target = *pan_orbit;
+        // the camera moves right and up around the focus with the keys
+        let mut delta = key_orbit;
         if rotation_move.length_squared() > 0.0 {
-            any = true;
-            let window = get_primary_window_size(&windows);
-            let delta_x = {
-                let delta = rotation_move.x / window.x * std::f32::consts::PI * 2.0;
-                if pan_orbit.upside_down {
-                    -delta
-                } else {
-                    delta
-                }
-            };
-            let delta_y = rotation_move.y / window.y * std::f32::consts::PI;
-            let yaw = Quat::from_rotation_y(-delta_x);
-            let pitch = Quat::from_rotation_x(-delta_y);
-            transform.rotation *= yaw; // rotate around global y axis
-            transform.rotation *= pitch; // rotate around local x axis
-        } else if pan.length_squared() > 0.0 {
-            any = true;
+            // yaw turns around the vertical axis like a turntable, pitch tilts up and down
+            delta += Vec2::new(
+                -rotation_move.x / window.x * PI * 2.0,
+                rotation_move.y / window.y * PI,
+            );
+        }
+        if delta.length_squared() > 0.0 {
+            target.yaw += delta.x;
+            target.pitch += delta.y;
+            if dt > 0.0 {
+                // averaged over a few frames, mouse deltas are too uneven on their own
+                smoothed.orbit_velocity = smoothed
+                    .orbit_velocity
+                    .lerp(delta / dt, damping(VELOCITY_SMOOTHING, dt));
+            }
+        } else {
+            if settings.orbit_inertia && !actions.pressed(Action::Orbit) {
+                target.yaw += smoothed.orbit_velocity.x * dt;
+                target.pitch += smoothed.orbit_velocity.y * dt;
+                smoothed.orbit_velocity *= 1.0 - damping(INERTIA_DECAY, dt);
+            } else {
+                // holding the button without moving stops the camera
+                smoothed.orbit_velocity = Vec2::ZERO;
+            }
+        }
+
+        // the focus moves right and up by this much times the radius
+        let mut pan_move = key_pan;
+        if pan.length_squared() > 0.0 {
             // make panning distance independent of resolution and FOV,
-            let window = get_primary_window_size(&windows);
             pan *= Vec2::new(projection.fov * projection.aspect_ratio, projection.fov) / window;
+            // the world follows the mouse
+            pan_move += Vec2::new(-pan.x, pan.y);
+        }
+        if pan_move.length_squared() > 0.0 {
             // translate by local axes
-            let right = transform.rotation * Vec3::X * -pan.x;
-            let up = transform.rotation * Vec3::Y * pan.y;
+            let rotation = target.rotation();
+            let right = rotation * Vec3::X * pan_move.x;
+            let up = rotation * Vec3::Y * pan_move.y;
             // make panning proportional to distance away from focus point
-            let translation = (right + up) * pan_orbit.radius;
-            pan_orbit.focus += translation;
-        } else if scroll.abs() > 0.0 {
-            any = true;
-            pan_orbit.radius -= scroll * pan_orbit.radius * 0.2;
-            // dont allow zoom to reach zero or you get stuck
-            pan_orbit.radius = f32::max(pan_orbit.radius, 0.05);
+            let translation = (right + up) * target.radius;
+            target.focus += translation;
+        }
+        if scroll.abs() > 0.0 {
+            target.radius -= scroll * target.radius * ZOOM_STEP;
         }
 
-        if any {
-            // emulating parent/child to make the yaw/y-axis rotation behave like a turntable
-            // parent = x and y rotation
-            // child = z-offset
-            let rot_matrix = Mat3::from_quat(transform.rotation);
-            transform.translation =
-                pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
+        // the limits change when the whale finishes loading, so they apply without input too
+        limits.clamp(&mut target);
+        let (min_pitch, max_pitch) = limits.pitch_range();
+        if target.pitch <= min_pitch || target.pitch >= max_pitch {
+            // dont keep drifting against a pitch limit
+            smoothed.orbit_velocity.y = 0.0;
+        }
+        if smoothed.orbit_velocity.length_squared() < MIN_INERTIA_SPEED * MIN_INERTIA_SPEED {
+            smoothed.orbit_velocity = Vec2::ZERO;
+        }
+        if target != *pan_orbit {
+            *pan_orbit = target;
         }
     }
 }
 
-fn get_primary_window_size(windows: &Res<Windows>) -> Vec2 {
-    let primary_window = windows.get_primary().unwrap();
-    Vec2::new(
-        primary_window.width() as f32,
-        primary_window.height() as f32,
-    )
+/// Moves the camera part of the way towards its `PanOrbitCamera` every frame. The part depends
+/// on the frame time, so the camera moves at the same pace at any frame rate.
+/// The camera is kept outside of the whale, whatever its radius says.
+pub fn follow_camera_target(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    meshes: Res<Assets<Mesh>>,
+    whale_query: Query<(&Handle<Mesh>, &GlobalTransform, &Aabb), With<Whale>>,
+    mut query: Query<(&PanOrbitCamera, &mut SmoothedCamera, &mut Transform)>,
+) {
+    let t = damping(settings.camera_smoothing, time.delta_seconds());
+    for (target, mut smoothed, mut transform) in query.iter_mut() {
+        if smoothed.current != *target {
+            smoothed.current = smoothed.current.approach(target, t);
+        }
+        let mut view = smoothed.current;
+        for (mesh, whale_transform, aabb) in whale_query.iter() {
+            if let Some(mesh) = meshes.get(mesh) {
+                view.radius = outside_distance(&view, mesh, whale_transform, aabb);
+            }
+        }
+        let next = view.transform();
+        if *transform != next {
+            *transform = next;
+        }
+    }
+}
+
+// Smallest distance from the camera to the whale's surface
+const MIN_SURFACE_DISTANCE: f32 = 0.3;
+
+// The radius of `view`, lengthened where the eye would be inside the whale or too close to its
+// surface. A ray cast from beyond the whale back towards the focus finds the outermost surface
+// on the camera's side.
+pub fn outside_distance(
+    view: &PanOrbitCamera,
+    mesh: &Mesh,
+    transform: &GlobalTransform,
+    aabb: &Aabb,
+) -> f32 {
+    let outwards = view.rotation() * Vec3::Z;
+    let center = transform.mul_vec3(aabb.center);
+    let bounding_radius = (aabb.half_extents * transform.scale).length();
+    // surely outside of the whale
+    let far = view.focus.distance(center) + bounding_radius * 2.0;
+    let ray = Ray3d::new(view.focus + outwards * far, -outwards);
+    match ray_intersection_over_mesh(mesh, &transform.compute_matrix(), &ray) {
+        // a surface behind the focus does not block the view
+        Some(hit) if hit.distance() < far => {
+            let surface = far - hit.distance();
+            view.radius.max(surface + MIN_SURFACE_DISTANCE)
+        }
+        _ => view.radius,
+    }
 }
 
 /// Spawn a camera like this
 pub fn spawn_camera(mut commands: Commands) {
-    let translation = Vec3::new(-2.0, 2.5, 5.0);
-    let radius = translation.length();
+    let pan_orbit = default_view();
 
     commands
         .spawn_bundle(PerspectiveCameraBundle {
-            transform: Transform::from_translation(translation).looking_at(Vec3::ZERO, Vec3::Y),
-            ..Default::default()
-        })
-        .insert(PanOrbitCamera {
-            radius,
+            transform: pan_orbit.transform(),
             ..Default::default()
         })
+        .insert(pan_orbit)
+        .insert(SmoothedCamera::new(pan_orbit))
         .insert(OnGameScreen)
+        .insert(GameCamera)
         .insert_bundle(PickingCameraBundle::default());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn looking_at_round_trips_the_eye_position() {
+        for eye in [
+            Vec3::new(-2.0, 2.5, 5.0),
+            Vec3::new(3.0, -1.0, -4.0),
+            Vec3::new(0.0, 0.0, 2.0),
+        ] {
+            let focus = Vec3::new(0.5, 0.5, -0.5);
+            let camera = PanOrbitCamera::looking_at(focus, eye);
+            assert_near(camera.eye(), eye);
+            // the camera looks down its -Z axis, at the focus
+            let forward = camera.rotation() * -Vec3::Z;
+            assert_near(forward, (focus - eye).normalize());
+        }
+    }
+
+    #[test]
+    fn positive_pitch_is_above_the_focus() {
+        let camera = PanOrbitCamera {
+            pitch: 0.5,
+            ..Default::default()
+        };
+        assert!(camera.eye().y > camera.focus.y);
+        // the camera stays upright
+        assert!((camera.rotation() * Vec3::Y).y > 0.0);
+    }
+
+    #[test]
+    fn pitch_is_clamped() {
+        let limits = CameraLimits::default();
+        let mut camera = PanOrbitCamera {
+            pitch: 2.0,
+            ..Default::default()
+        };
+        limits.clamp(&mut camera);
+        assert_eq!(camera.pitch, MAX_PITCH);
+        camera.pitch = -2.0;
+        limits.clamp(&mut camera);
+        assert_eq!(camera.pitch, -MAX_PITCH);
+    }
+
+    #[test]
+    fn configured_pitch_never_flips_the_camera() {
+        let limits = CameraLimits {
+            pitch: (-PI, PI),
+            ..Default::default()
+        };
+        let mut camera = PanOrbitCamera {
+            pitch: 3.0,
+            ..Default::default()
+        };
+        limits.clamp(&mut camera);
+        assert_eq!(camera.pitch, FRAC_PI_2);
+    }
+
+    #[test]
+    fn radius_range_follows_the_whale_size() {
+        let half_extents = Vec3::new(2.0, 1.0, 3.0);
+        let limits = CameraLimits::around(Vec3::ZERO, half_extents);
+        assert_eq!(limits.radius.0, 3.0);
+        assert_eq!(limits.radius.1, half_extents.length() * MAX_RADIUS_FACTOR);
+
+        let mut camera = PanOrbitCamera {
+            radius: 0.1,
+            ..Default::default()
+        };
+        limits.clamp(&mut camera);
+        assert_eq!(camera.radius, 3.0);
+        camera.radius = 1000.0;
+        limits.clamp(&mut camera);
+        assert_eq!(camera.radius, limits.radius.1);
+    }
+
+    #[test]
+    fn focus_stays_around_the_whale() {
+        let center = Vec3::new(0.0, 0.5, 0.0);
+        let limits = CameraLimits::around(center, Vec3::new(2.0, 1.0, 3.0));
+        let mut camera = PanOrbitCamera {
+            focus: Vec3::new(100.0, -100.0, 1.0),
+            ..Default::default()
+        };
+        limits.clamp(&mut camera);
+        assert_eq!(camera.focus, Vec3::new(3.0, -1.0, 1.0));
+
+        // a focus inside the region is left alone
+        let inside = center + Vec3::new(1.0, 0.2, -2.0);
+        camera.focus = inside;
+        limits.clamp(&mut camera);
+        assert_eq!(camera.focus, inside);
+    }
+
+    #[test]
+    fn damping_does_not_depend_on_the_frame_rate() {
+        let start = PanOrbitCamera::default();
+        let target = PanOrbitCamera::looking_at(Vec3::new(1.0, 0.0, 0.0), Vec3::new(3.0, 4.0, 2.0));
+        let follow = |frames: usize| {
+            let dt = 0.5 / frames as f32;
+            (0..frames).fold(start, |current, _| {
+                current.approach(&target, damping(0.1, dt))
+            })
+        };
+        let slow = follow(15);
+        let fast = follow(240);
+        assert_near(slow.eye(), fast.eye());
+        assert!((slow.radius - fast.radius).abs() < 1e-4);
+    }
+
+    #[test]
+    fn no_smoothing_follows_instantly() {
+        let target = PanOrbitCamera::looking_at(Vec3::ONE, Vec3::new(3.0, 4.0, 2.0));
+        let current = PanOrbitCamera::default().approach(&target, damping(0.0, 0.016));
+        assert_eq!(current, target);
+    }
+
+    #[test]
+    fn transitions_ease_between_views_the_short_way_around() {
+        let from = PanOrbitCamera {
+            yaw: 3.0,
+            ..Default::default()
+        };
+        let to = PanOrbitCamera {
+            yaw: -3.0,
+            radius: 8.0,
+            ..Default::default()
+        };
+        let transition = CameraTransition::new(from, to);
+        assert_eq!(transition.at(0.0), from);
+        let end = transition.at(TRANSITION_SECONDS);
+        assert_near(end.eye(), to.eye());
+        // through yaw = π, not through 0
+        let middle = transition.at(TRANSITION_SECONDS / 2.0);
+        assert!(middle.yaw > 3.0);
+    }
+
+    #[test]
+    fn camera_stays_outside_the_whale() {
+        let mesh = Mesh::from(shape::Cube { size: 2.0 });
+        let transform = GlobalTransform::from_xyz(0.0, 1.0, 0.0);
+        let aabb = Aabb {
+            center: Vec3::ZERO,
+            half_extents: Vec3::ONE,
+        };
+        let inside = PanOrbitCamera {
+            focus: Vec3::new(0.0, 1.0, 0.0),
+            radius: 0.5,
+            ..Default::default()
+        };
+        let radius = outside_distance(&inside, &mesh, &transform, &aabb);
+        assert!((radius - (1.0 + MIN_SURFACE_DISTANCE)).abs() < 1e-4);
+
+        let outside = PanOrbitCamera {
+            radius: 5.0,
+            ..inside
+        };
+        assert_eq!(outside_distance(&outside, &mesh, &transform, &aabb), 5.0);
+
+        // the whale behind the focus is not in the way
+        let in_front = PanOrbitCamera {
+            focus: Vec3::new(0.0, 1.0, 3.0),
+            radius: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(outside_distance(&in_front, &mesh, &transform, &aabb), 0.5);
+    }
+}
@@ -0,0 +1,128 @@
+// The clock of the run shown on the HUD, which is `Run::elapsed`: splits when a wave begins
+// and keeps the longest run of every mode as the personal best to race against
+
+use super::super::settings::{storage, Difficulty};
+use super::hud::{Toast, ToastPriority};
+use super::replay::Playback;
+use super::Run;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const PERSONAL_BESTS_KEY: &str = "personal-bests";
+
+/// The longest run of a mode
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PersonalBest {
+    pub time: f32,
+    pub splits: Vec<f32>,
+}
+
+/// Personal bests per mode, loaded at startup and saved when one is beaten
+#[derive(Default, Serialize, Deserialize)]
+pub struct PersonalBests(pub HashMap<Difficulty, PersonalBest>);
+
+/// `seconds` as minutes, seconds and hundredths, like 01:23.45
+pub fn clock_text(seconds: f32) -> String {
+    let hundredths = (seconds.max(0.0) * 100.0).floor() as u64;
+    format!(
+        "{:02}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+/// Signed `clock_text` of the difference between a run and the best run, + once ahead
+pub fn delta_text(delta: f32) -> String {
+    let sign = if delta < 0.0 { '-' } else { '+' };
+    format!("{}{}", sign, clock_text(delta.abs()))
+}
+
+pub fn load_personal_bests(mut commands: Commands) {
+    let bests = match storage::read(PERSONAL_BESTS_KEY) {
+        Ok(contents) => ron::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Could not read the personal bests: {}", e);
+            PersonalBests::default()
+        }),
+        Err(_) => PersonalBests::default(),
+    };
+    commands.insert_resource(bests);
+}
+
+// Splits when the spawns enter a new wave, and announces it
+pub fn split_waves(mut run: ResMut<Run>, mut toasts: EventWriter<Toast>) {
+    let wave = match run.split_wave() {
+        Some(wave) => wave,
+        None => return,
+    };
+    toasts.send(
+        Toast::new(ToastPriority::Normal, "toast-wave")
+            .with_arg("wave", wave)
+            .with_arg("time", clock_text(run.elapsed)),
+    );
+}
+
+// Keeps the time of the finished run when it beat the best of its mode.
+// Played back runs don't count.
+pub fn finish_run(run: Res<Run>, playback: Res<Playback>, mut bests: ResMut<PersonalBests>) {
+    if playback.is_playing() {
+        return;
+    }
+    let best = bests.0.get(&run.mode);
+    if best.is_some_and(|best| best.time >= run.elapsed) {
+        return;
+    }
+    bests.0.insert(
+        run.mode,
+        PersonalBest {
+            time: run.elapsed,
+            splits: run.splits.clone(),
+        },
+    );
+    let result = ron::ser::to_string_pretty(&*bests, Default::default())
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            storage::write(PERSONAL_BESTS_KEY, &contents).map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        warn!("Could not save the personal bests: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::BARNACLES_PER_WAVE;
+    use super::*;
+
+    #[test]
+    fn clock_text_shows_minutes_seconds_and_hundredths() {
+        assert_eq!(clock_text(0.0), "00:00.00");
+        assert_eq!(clock_text(5.256), "00:05.25");
+        assert_eq!(clock_text(83.456), "01:23.45");
+        assert_eq!(clock_text(3599.995), "59:59.99");
+    }
+
+    #[test]
+    fn delta_text_is_signed() {
+        assert_eq!(delta_text(-12.5), "-00:12.50");
+        assert_eq!(delta_text(61.0), "+01:01.00");
+    }
+
+    #[test]
+    fn splits_record_the_start_of_every_wave() {
+        let mut run = Run {
+            spawned: BARNACLES_PER_WAVE - 1,
+            elapsed: 20.0,
+            ..Default::default()
+        };
+        assert_eq!(run.split_wave(), None);
+        run.spawned += 1;
+        assert_eq!(run.split_wave(), Some(2));
+        assert_eq!(run.split_wave(), None);
+        run.elapsed = 35.0;
+        run.spawned += BARNACLES_PER_WAVE;
+        assert_eq!(run.split_wave(), Some(3));
+        assert_eq!(run.splits, [20.0, 35.0]);
+    }
+}
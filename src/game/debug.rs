@@ -0,0 +1,144 @@
+// Overlay toggled with F3 showing what the game is doing: frame rate, entities, barnacles, the
+// next spawn and the camera
+
+use super::super::GameState;
+use super::camera::PanOrbitCamera;
+use super::{despawn_screen, Barnacle, BarnacleSpawnTimer, BarnacleStatus, GameCamera};
+use bevy::diagnostic::{
+    DiagnosticId, Diagnostics, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin,
+};
+use bevy::prelude::*;
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+
+/// Whether the overlay is shown, kept across runs
+#[derive(Default)]
+pub struct DebugOverlay {
+    pub shown: bool,
+}
+
+#[derive(Component)]
+struct DebugOverlayText;
+
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>()
+            .add_plugin(FrameTimeDiagnosticsPlugin)
+            .add_plugin(EntityCountDiagnosticsPlugin)
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_overlay))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(toggle_overlay)
+                    .with_system(update_overlay),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Game).with_system(despawn_screen::<DebugOverlayText>),
+            );
+    }
+}
+
+fn spawn_overlay(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    overlay: Res<DebugOverlay>,
+) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Kenney Future Narrow.ttf"),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            visibility: Visibility {
+                is_visible: overlay.shown,
+            },
+            ..Default::default()
+        })
+        .insert(DebugOverlayText);
+}
+
+fn toggle_overlay(
+    keys: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut query: Query<&mut Visibility, With<DebugOverlayText>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    overlay.shown = !overlay.shown;
+    for mut visibility in query.iter_mut() {
+        visibility.is_visible = overlay.shown;
+    }
+}
+
+fn diagnostic(diagnostics: &Diagnostics, id: DiagnosticId) -> f64 {
+    diagnostics
+        .get(id)
+        .and_then(|diagnostic| diagnostic.average())
+        .unwrap_or(0.0)
+}
+
+fn update_overlay(
+    overlay: Res<DebugOverlay>,
+    diagnostics: Res<Diagnostics>,
+    barnacle_query: Query<&Barnacle>,
+    spawn_query: Query<&BarnacleSpawnTimer>,
+    camera_query: Query<&PanOrbitCamera, With<GameCamera>>,
+    mut text_query: Query<&mut Text, With<DebugOverlayText>>,
+) {
+    if !overlay.shown {
+        return;
+    }
+    let fps = diagnostic(&diagnostics, FrameTimeDiagnosticsPlugin::FPS);
+    let frame_time = diagnostic(&diagnostics, FrameTimeDiagnosticsPlugin::FRAME_TIME) * 1000.0;
+    let entities = diagnostic(&diagnostics, EntityCountDiagnosticsPlugin::ENTITY_COUNT);
+    let mut lines = vec![
+        format!("FPS {:.0} ({:.2} ms)", fps, frame_time),
+        format!("Entities {:.0}", entities),
+    ];
+
+    let mut counts = [0; 3];
+    for barnacle in barnacle_query.iter() {
+        counts[match barnacle.status {
+            BarnacleStatus::Attaching => 0,
+            BarnacleStatus::Attached => 1,
+            BarnacleStatus::Gone => 2,
+        }] += 1;
+    }
+    lines.push(format!(
+        "Barnacles attaching {} attached {} gone {}",
+        counts[0], counts[1], counts[2]
+    ));
+    for timer in spawn_query.iter() {
+        lines.push(format!(
+            "Next spawn in {:.2} s",
+            timer.0.duration().as_secs_f32() - timer.0.elapsed_secs()
+        ));
+    }
+    for camera in camera_query.iter() {
+        lines.push(format!(
+            "Camera focus ({:.2}, {:.2}, {:.2}) radius {:.2}",
+            camera.focus.x, camera.focus.y, camera.focus.z, camera.radius
+        ));
+    }
+
+    let text = lines.join("\n");
+    for mut overlay_text in text_query.iter_mut() {
+        overlay_text.sections[0].value = text.clone();
+    }
+}
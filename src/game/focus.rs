@@ -0,0 +1,122 @@
+// Points the camera at the barnacles closest to attaching, one after the other, and back at
+// the whole whale
+
+use super::super::actions::Action;
+use super::camera::{self, CameraLimits, CameraTransition, PanOrbitCamera, SmoothedCamera};
+use super::{Barnacle, BarnacleAttachedTimer, BarnacleStatus, Whale};
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+
+// Distance to a framed barnacle, relative to the closest the camera may get to its focus
+const FOCUS_RADIUS_FACTOR: f32 = 1.2;
+
+/// The barnacle the camera was last pointed at with `Action::FocusBarnacle`
+#[derive(Default)]
+pub struct BarnacleFocus(pub Option<Entity>);
+
+/// Asks for the camera to frame this barnacle, like `Action::FocusBarnacle` does
+pub struct FocusOn(pub Entity);
+
+pub fn reset_barnacle_focus(mut focus: ResMut<BarnacleFocus>) {
+    focus.0 = None;
+}
+
+// The attaching barnacles in the order `Action::FocusBarnacle` visits them: least time
+// left first
+fn focus_order(barnacles: impl Iterator<Item = (Entity, f32)>) -> Vec<Entity> {
+    let mut barnacles: Vec<(Entity, f32)> = barnacles.collect();
+    barnacles.sort_by(|a, b| a.1.total_cmp(&b.1));
+    barnacles.into_iter().map(|(entity, _)| entity).collect()
+}
+
+// The view looking at `point` from outside the whale, straight towards its center
+fn frame_point(
+    current: &PanOrbitCamera,
+    whale_center: Vec3,
+    point: Vec3,
+    radius: f32,
+) -> PanOrbitCamera {
+    let outwards = (point - whale_center).normalize_or_zero();
+    if outwards == Vec3::ZERO {
+        return PanOrbitCamera {
+            focus: point,
+            ..*current
+        };
+    }
+    PanOrbitCamera::looking_at(point, point + outwards * radius)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn focus_barnacle(
+    mut commands: Commands,
+    actions: Res<Input<Action>>,
+    limits: Res<CameraLimits>,
+    mut focus: ResMut<BarnacleFocus>,
+    mut requests: EventReader<FocusOn>,
+    barnacle_query: Query<(Entity, &Barnacle, &BarnacleAttachedTimer, &GlobalTransform)>,
+    whale_query: Query<(&GlobalTransform, Option<&Aabb>), With<Whale>>,
+    mut camera_query: Query<(Entity, &PanOrbitCamera, &mut SmoothedCamera)>,
+) {
+    let mut target = None;
+    let requested = requests.iter().last().map(|FocusOn(barnacle)| *barnacle);
+    let next = if requested.is_some() {
+        requested
+    } else if actions.just_pressed(Action::FocusBarnacle) {
+        let order = focus_order(
+            barnacle_query
+                .iter()
+                .filter(|(_, barnacle, _, _)| barnacle.status == BarnacleStatus::Attaching)
+                .map(|(entity, _, timer, _)| {
+                    (
+                        entity,
+                        timer.0.duration().as_secs_f32() - timer.0.elapsed_secs(),
+                    )
+                }),
+        );
+        match focus
+            .0
+            .and_then(|current| order.iter().position(|e| *e == current))
+        {
+            Some(index) => order.get((index + 1) % order.len()).copied(),
+            None => order.first().copied(),
+        }
+    } else {
+        None
+    };
+    if let Some(next) = next {
+        focus.0 = Some(next);
+        let whale_center = whale_query
+            .iter()
+            .next()
+            .map_or(Vec3::ZERO, |(transform, aabb)| {
+                transform.mul_vec3(aabb.map_or(Vec3::ZERO, |aabb| aabb.center))
+            });
+        if let Ok((_, _, _, transform)) = barnacle_query.get(next) {
+            target = Some((whale_center, transform.translation));
+        }
+    } else if actions.just_pressed(Action::ResetView) {
+        focus.0 = None;
+    } else {
+        return;
+    }
+
+    for (entity, pan_orbit, mut smoothed) in camera_query.iter_mut() {
+        let mut view = match target {
+            Some((whale_center, point)) => frame_point(
+                pan_orbit,
+                whale_center,
+                point,
+                limits.radius.0 * FOCUS_RADIUS_FACTOR,
+            ),
+            None if focus.0.is_none() => camera::default_view(),
+            // no barnacle to look at
+            None => continue,
+        };
+        limits.clamp(&mut view);
+        // drifting would end the transition right away
+        smoothed.orbit_velocity = Vec2::ZERO;
+        commands
+            .entity(entity)
+            .insert(CameraTransition::new(*pan_orbit, view));
+    }
+}
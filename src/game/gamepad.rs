@@ -0,0 +1,82 @@
+use super::super::actions::ActiveGamepad;
+use super::OnGameScreen;
+use bevy::prelude::*;
+use bevy_mod_picking::UpdatePicks;
+
+// Cursor speed at full stick deflection, in pixels per second
+const CURSOR_SPEED: f32 = 600.0;
+const CURSOR_SIZE: f32 = 16.0;
+
+/// On screen cursor moved with the left stick. It feeds the picking ray like the mouse does,
+/// so the `Remove` action works on the barnacle under it.
+#[derive(Component)]
+pub struct VirtualCursor {
+    position: Vec2,
+}
+
+pub fn spawn_virtual_cursor(mut commands: Commands, windows: Res<Windows>) {
+    let position = windows
+        .get_primary()
+        .map(|window| Vec2::new(window.width(), window.height()) / 2.0)
+        .unwrap_or_default();
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(CURSOR_SIZE), Val::Px(CURSOR_SIZE)),
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            color: Color::rgba(0.9, 0.9, 0.5, 0.8).into(),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(VirtualCursor { position })
+        .insert(OnGameScreen);
+}
+
+pub fn move_virtual_cursor(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    gamepad: Res<ActiveGamepad>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut cursor_query: Query<(&mut VirtualCursor, &mut Style, &mut Visibility)>,
+    mut picking_query: Query<&mut UpdatePicks>,
+) {
+    for (mut cursor, mut style, mut visibility) in cursor_query.iter_mut() {
+        // only shown while a gamepad is plugged in
+        let shown = !visibility.is_visible && gamepad.0.is_some();
+        if visibility.is_visible != gamepad.0.is_some() {
+            visibility.is_visible = gamepad.0.is_some();
+        }
+        let gamepad = match gamepad.0 {
+            Some(gamepad) => gamepad,
+            None => continue,
+        };
+
+        let stick = Vec2::new(
+            axes.get(GamepadAxis(gamepad, GamepadAxisType::LeftStickX))
+                .unwrap_or(0.0),
+            axes.get(GamepadAxis(gamepad, GamepadAxisType::LeftStickY))
+                .unwrap_or(0.0),
+        );
+        if stick == Vec2::ZERO && !shown {
+            continue;
+        }
+
+        let window = match windows.get_primary() {
+            Some(window) => Vec2::new(window.width(), window.height()),
+            None => continue,
+        };
+        cursor.position = (cursor.position + stick * CURSOR_SPEED * time.delta_seconds())
+            .clamp(Vec2::ZERO, window);
+        style.position = Rect {
+            left: Val::Px(cursor.position.x - CURSOR_SIZE / 2.0),
+            bottom: Val::Px(cursor.position.y - CURSOR_SIZE / 2.0),
+            ..Default::default()
+        };
+        // the picking plugin keeps using this position until the mouse moves
+        for mut update_picks in picking_query.iter_mut() {
+            *update_picks = UpdatePicks::EveryFrame(cursor.position);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+// Arrows at the edge of the screen pointing at the attaching barnacles the player can't see,
+// either outside of the view or hidden behind the whale
+
+use super::super::{Barnacle, BarnacleAttachedTimer, BarnacleStatus};
+use super::sight::{Sighting, Sightings};
+use super::HUDRelated;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+const ARROW_SIZE: f32 = 32.0;
+// Distance of the arrows from the window border
+const EDGE_MARGIN: f32 = 24.0;
+// Colors of a barnacle that just started attaching and one about to attach
+const CALM_COLOR: Color = Color::rgb(0.9, 0.9, 0.5);
+const URGENT_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+
+/// Points at the barnacle entity it was spawned for
+#[derive(Component)]
+pub struct EdgeArrow(Entity);
+
+pub fn load_arrow_image(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ArrowImage(asset_server.load("textures/arrow.png")));
+}
+
+pub struct ArrowImage(Handle<Image>);
+
+/// Where an arrow goes on the screen, in pixels from the bottom left corner, and the angle it
+/// points at counter-clockwise from the right
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArrowPlacement {
+    pub position: Vec2,
+    pub angle: f32,
+}
+
+/// The arrow for a barnacle seen as `sighting`, `None` when it is in view.
+/// Barnacles behind the camera get an arrow on the edge of the side to turn to.
+pub fn place_arrow(sighting: Sighting, window: Vec2) -> Option<ArrowPlacement> {
+    if sighting.on_screen() {
+        if !sighting.occluded {
+            return None;
+        }
+        // right next to the hidden barnacle, pointing at it from the center of the screen
+        let target = sighting.screen_position(window);
+        let direction = (target - window / 2.0).normalize_or_zero();
+        let direction = if direction == Vec2::ZERO {
+            Vec2::Y
+        } else {
+            direction
+        };
+        return Some(ArrowPlacement {
+            position: target - direction * ARROW_SIZE,
+            angle: direction.y.atan2(direction.x),
+        });
+    }
+
+    let mut direction = sighting.ndc;
+    if direction == Vec2::ZERO {
+        direction = -Vec2::Y;
+    }
+    // scale the direction until it reaches the border of the area the arrows stay in
+    let half = (window / 2.0 - Vec2::splat(EDGE_MARGIN)).max(Vec2::ZERO);
+    let pixels = direction * window / 2.0;
+    let scale = (half.x / pixels.x.abs()).min(half.y / pixels.y.abs());
+    Some(ArrowPlacement {
+        position: window / 2.0 + pixels * scale,
+        angle: pixels.y.atan2(pixels.x),
+    })
+}
+
+/// Color of the arrow of a barnacle with `fraction_left` of its attaching time left
+pub fn urgency_color(fraction_left: f32) -> Color {
+    let t = 1.0 - fraction_left.clamp(0.0, 1.0);
+    let calm = Vec4::from(CALM_COLOR);
+    let urgent = Vec4::from(URGENT_COLOR);
+    Color::from(calm.lerp(urgent, t))
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_edge_arrows(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    image: Res<ArrowImage>,
+    sightings: Res<Sightings>,
+    barnacle_query: Query<(Entity, &Barnacle, &BarnacleAttachedTimer)>,
+    mut arrow_query: Query<(
+        Entity,
+        &EdgeArrow,
+        &mut Style,
+        &mut Transform,
+        &mut UiColor,
+        &mut Visibility,
+    )>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => Vec2::new(window.width(), window.height()),
+        None => return,
+    };
+    let mut arrows: HashMap<Entity, Entity> = HashMap::new();
+    for (arrow, EdgeArrow(barnacle), ..) in arrow_query.iter() {
+        let attaching = barnacle_query
+            .get(*barnacle)
+            .is_ok_and(|(_, b, _)| b.status == BarnacleStatus::Attaching);
+        if attaching {
+            arrows.insert(*barnacle, arrow);
+        } else {
+            commands.entity(arrow).despawn_recursive();
+        }
+    }
+
+    for (entity, barnacle, timer) in barnacle_query.iter() {
+        if barnacle.status != BarnacleStatus::Attaching {
+            continue;
+        }
+        let placement = sightings
+            .0
+            .get(&entity)
+            .and_then(|sighting| place_arrow(*sighting, window));
+        let fraction_left = 1.0 - timer.0.percent();
+
+        let arrow = match arrows.get(&entity) {
+            Some(arrow) => *arrow,
+            None => {
+                if placement.is_none() {
+                    continue;
+                }
+                commands
+                    .spawn_bundle(ImageBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(ARROW_SIZE), Val::Px(ARROW_SIZE)),
+                            position_type: PositionType::Absolute,
+                            ..Default::default()
+                        },
+                        image: image.0.clone().into(),
+                        visibility: Visibility { is_visible: false },
+                        ..Default::default()
+                    })
+                    .insert(EdgeArrow(entity))
+                    .insert(HUDRelated);
+                // placed from the next frame on
+                continue;
+            }
+        };
+        let (_, _, mut style, mut arrow_transform, mut color, mut visibility) =
+            match arrow_query.get_mut(arrow) {
+                Ok(arrow) => arrow,
+                Err(_) => continue,
+            };
+        match placement {
+            Some(placement) => {
+                style.position = Rect {
+                    left: Val::Px(placement.position.x - ARROW_SIZE / 2.0),
+                    bottom: Val::Px(placement.position.y - ARROW_SIZE / 2.0),
+                    ..Default::default()
+                };
+                // the layout only moves nodes, the rotation stays
+                arrow_transform.rotation = Quat::from_rotation_z(placement.angle);
+                *color = urgency_color(fraction_left).into();
+                visibility.is_visible = true;
+            }
+            None => {
+                if visibility.is_visible {
+                    visibility.is_visible = false;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen() -> Vec2 {
+        Vec2::new(800.0, 600.0)
+    }
+
+    fn sighting(ndc: Vec2, behind: bool, occluded: bool) -> Sighting {
+        Sighting {
+            ndc,
+            behind,
+            occluded,
+        }
+    }
+
+    fn inside(position: Vec2, window: Vec2) -> bool {
+        position.x >= 0.0 && position.y >= 0.0 && position.x <= window.x && position.y <= window.y
+    }
+
+    #[test]
+    fn visible_barnacles_get_no_arrow() {
+        assert_eq!(
+            place_arrow(sighting(Vec2::new(0.5, -0.5), false, false), screen()),
+            None
+        );
+    }
+
+    #[test]
+    fn arrows_point_at_barnacles_out_of_view() {
+        let arrow = place_arrow(sighting(Vec2::new(3.0, 0.0), false, false), screen()).unwrap();
+        assert_eq!(
+            arrow.position,
+            Vec2::new(screen().x - EDGE_MARGIN, screen().y / 2.0)
+        );
+        assert_eq!(arrow.angle, 0.0);
+
+        let arrow = place_arrow(sighting(Vec2::new(0.0, -2.0), false, false), screen()).unwrap();
+        assert_eq!(arrow.position, Vec2::new(screen().x / 2.0, EDGE_MARGIN));
+    }
+
+    #[test]
+    fn barnacles_behind_the_camera_get_an_arrow() {
+        let arrow = place_arrow(sighting(Vec2::new(0.5, 0.0), true, false), screen()).unwrap();
+        assert_eq!(
+            arrow.position,
+            Vec2::new(screen().x - EDGE_MARGIN, screen().y / 2.0)
+        );
+        // straight behind the camera
+        let arrow = place_arrow(sighting(Vec2::ZERO, true, false), screen()).unwrap();
+        assert_eq!(arrow.position, Vec2::new(screen().x / 2.0, EDGE_MARGIN));
+    }
+
+    #[test]
+    fn arrows_of_occluded_barnacles_sit_next_to_them() {
+        let arrow = place_arrow(sighting(Vec2::new(0.5, 0.0), false, true), screen()).unwrap();
+        assert_eq!(arrow.position, Vec2::new(600.0 - ARROW_SIZE, 300.0));
+        assert_eq!(arrow.angle, 0.0);
+        // in the middle of the screen there is no direction to point from, it points up at it
+        let arrow = place_arrow(sighting(Vec2::ZERO, false, true), screen()).unwrap();
+        assert_eq!(arrow.position, screen() / 2.0 - Vec2::Y * ARROW_SIZE);
+    }
+
+    #[test]
+    fn arrows_stay_in_tiny_windows() {
+        let window = Vec2::new(20.0, 10.0);
+        for ndc in [Vec2::new(5.0, 1.0), Vec2::new(-3.0, -7.0), Vec2::ZERO] {
+            let arrow = place_arrow(sighting(ndc, true, false), window).unwrap();
+            assert!(arrow.position.is_finite());
+            assert!(inside(arrow.position, window), "{:?}", arrow.position);
+        }
+    }
+
+    #[test]
+    fn urgency_goes_from_calm_to_urgent() {
+        let same = |a: Color, b: Color| Vec4::from(a).abs_diff_eq(Vec4::from(b), 1e-5);
+        assert!(same(urgency_color(1.0), CALM_COLOR));
+        assert!(same(urgency_color(0.0), URGENT_COLOR));
+        assert!(same(urgency_color(2.0), CALM_COLOR));
+        assert!(same(urgency_color(-1.0), URGENT_COLOR));
+        let halfway = Vec4::from(urgency_color(0.5));
+        assert!(halfway.y < Vec4::from(CALM_COLOR).y && halfway.y > Vec4::from(URGENT_COLOR).y);
+    }
+}
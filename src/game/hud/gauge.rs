@@ -0,0 +1,263 @@
+// Bar filling up as barnacles attach, toward the count at which the whale is overrun, with a
+// projection of when that happens at the current pace
+
+use super::super::super::locale::Localization;
+use super::super::super::settings::Settings;
+use super::super::{BarnacleCount, Run};
+use super::toast::{Toast, ToastPriority};
+use super::{HUDRelated, TEXT_COLOR};
+use bevy::prelude::*;
+use fluent::FluentArgs;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+const BAR_WIDTH: f32 = 400.0;
+const BAR_HEIGHT: f32 = 24.0;
+// Fill levels where the bar turns orange, then red and pulsing
+const WARNING_LEVEL: f32 = 0.5;
+const DANGER_LEVEL: f32 = 0.75;
+const SAFE_COLOR: Color = Color::rgb(0.3, 0.7, 0.3);
+const WARNING_COLOR: Color = Color::rgb(0.9, 0.6, 0.1);
+const DANGER_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+// Pulses per second once in danger
+const PULSE_RATE: f32 = 1.5;
+// The projection looks at the growth over this many seconds, sampled this often
+const HISTORY_SECONDS: f32 = 20.0;
+const SAMPLE_SECONDS: f32 = 0.5;
+
+#[derive(Component)]
+pub struct GaugeFill;
+
+#[derive(Component)]
+pub struct OverrunText;
+
+/// Attached barnacle counts over the last `HISTORY_SECONDS` of the run
+#[derive(Default)]
+pub struct InfestationHistory {
+    samples: VecDeque<(f32, u32)>,
+}
+
+impl InfestationHistory {
+    pub fn record(&mut self, time: f32, count: u32) {
+        if self
+            .samples
+            .back()
+            .is_some_and(|(last, _)| time - last < SAMPLE_SECONDS)
+        {
+            return;
+        }
+        self.samples.push_back((time, count));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(first, _)| time - first > HISTORY_SECONDS)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Attached barnacles gained per second, over the recorded history
+    pub fn growth_rate(&self) -> f32 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((t0, c0)), Some((t1, c1))) if t1 > t0 => (*c1 as f32 - *c0 as f32) / (t1 - t0),
+            _ => 0.0,
+        }
+    }
+
+    /// Seconds until `threshold` attached barnacles at the recent pace, `None` while the count
+    /// isn't growing
+    pub fn time_to_overrun(&self, count: u32, threshold: u32) -> Option<f32> {
+        let rate = self.growth_rate();
+        if count >= threshold {
+            Some(0.0)
+        } else if rate > 0.0 {
+            Some((threshold - count) as f32 / rate)
+        } else {
+            None
+        }
+    }
+}
+
+/// Color of the bar filled to `level`, pulsing `time` seconds into the run once in danger
+pub fn gauge_color(level: f32, time: f32) -> Color {
+    if level < WARNING_LEVEL {
+        SAFE_COLOR
+    } else if level < DANGER_LEVEL {
+        WARNING_COLOR
+    } else {
+        let pulse = 0.75 + 0.25 * (time * PULSE_RATE * 2.0 * PI).sin();
+        Color::from(Vec4::from(DANGER_COLOR) * Vec4::new(pulse, pulse, pulse, 1.0))
+    }
+}
+
+pub fn reset_history(mut history: ResMut<InfestationHistory>) {
+    *history = InfestationHistory::default();
+}
+
+pub fn spawn_gauge(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Kenney Future.ttf"),
+        font_size: 20.0,
+        color: TEXT_COLOR,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(20.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(HUDRelated)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(BAR_WIDTH), Val::Px(BAR_HEIGHT)),
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.1, 0.1, 0.1, 0.7).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..Default::default()
+                            },
+                            color: SAFE_COLOR.into(),
+                            ..Default::default()
+                        })
+                        .insert(GaugeFill);
+                });
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(4.0)),
+                        ..Default::default()
+                    },
+                    text: Text::with_section("", text_style, Default::default()),
+                    ..Default::default()
+                })
+                .insert(OverrunText);
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_gauge(
+    run: Res<Run>,
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    barnacle_count: Res<BarnacleCount>,
+    mut history: ResMut<InfestationHistory>,
+    mut toasts: EventWriter<Toast>,
+    mut warned: Local<u8>,
+    mut fill_query: Query<(&mut Style, &mut UiColor), With<GaugeFill>>,
+    mut text_query: Query<&mut Text, With<OverrunText>>,
+) {
+    let count = barnacle_count.count;
+    let threshold = run.mode.overrun_count();
+    history.record(run.elapsed, count);
+    let level = (count as f32 / threshold as f32).min(1.0);
+    // warn once per threshold crossed, again after dropping back below it
+    let band = if level >= DANGER_LEVEL {
+        2
+    } else if level >= WARNING_LEVEL {
+        1
+    } else {
+        0
+    };
+    if band > *warned {
+        let toast = match band {
+            1 => Toast::new(ToastPriority::Normal, "toast-overrun-warning"),
+            _ => Toast::new(ToastPriority::High, "toast-overrun-danger"),
+        };
+        toasts.send(toast);
+    }
+    *warned = band;
+
+    for (mut style, mut color) in fill_query.iter_mut() {
+        let width = Val::Percent(level * 100.0);
+        if style.size.width != width {
+            style.size.width = width;
+        }
+        color.0 = gauge_color(level, run.elapsed);
+    }
+
+    let text = match history.time_to_overrun(count, threshold) {
+        Some(seconds) => {
+            let mut args = FluentArgs::new();
+            args.set("seconds", format!("{:.0}", seconds.ceil()));
+            localization.format(settings.language, "hud-overrun", Some(&args))
+        }
+        None => localization.get(settings.language, "hud-overrun-steady"),
+    };
+    for mut overrun_text in text_query.iter_mut() {
+        if overrun_text.sections[0].value != text {
+            overrun_text.sections[0].value = text.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Records a count every frame at 60 frames per second, `count(t)` attached at second t
+    fn history(seconds: f32, count: impl Fn(f32) -> u32) -> InfestationHistory {
+        let mut history = InfestationHistory::default();
+        for frame in 0..=(seconds * 60.0) as u32 {
+            let time = frame as f32 / 60.0;
+            history.record(time, count(time));
+        }
+        history
+    }
+
+    #[test]
+    fn steady_growth_projects_the_overrun() {
+        // one more barnacle every two seconds
+        let history = history(10.0, |t| (t / 2.0) as u32);
+        assert!((history.growth_rate() - 0.5).abs() < 0.05);
+        let eta = history.time_to_overrun(5, 10).unwrap();
+        assert!((eta - 10.0).abs() < 1.0, "{}", eta);
+    }
+
+    #[test]
+    fn only_recent_growth_counts() {
+        // a burst early on, then nothing for longer than the history goes back
+        let history = history(HISTORY_SECONDS + 10.0, |t| if t < 5.0 { 0 } else { 8 });
+        assert_eq!(history.growth_rate(), 0.0);
+        assert_eq!(history.time_to_overrun(8, 10), None);
+    }
+
+    #[test]
+    fn shrinking_count_holds_steady() {
+        let history = history(10.0, |t| 10 - t as u32);
+        assert!(history.growth_rate() < 0.0);
+        assert_eq!(history.time_to_overrun(0, 10), None);
+    }
+
+    #[test]
+    fn reaching_the_threshold_is_overrun_now() {
+        let history = InfestationHistory::default();
+        assert_eq!(history.time_to_overrun(12, 10), Some(0.0));
+    }
+
+    #[test]
+    fn color_warns_then_pulses() {
+        assert_eq!(gauge_color(0.2, 0.0), SAFE_COLOR);
+        assert_eq!(gauge_color(0.6, 0.0), WARNING_COLOR);
+        let bright = gauge_color(0.9, 0.25 / PULSE_RATE);
+        let dim = gauge_color(0.9, 0.75 / PULSE_RATE);
+        assert!(bright.r() > dim.r());
+    }
+}
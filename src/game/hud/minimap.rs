@@ -0,0 +1,282 @@
+// Flat map of the whale's skin in a corner of the HUD, laid out like its texture, with a dot
+// for every barnacle. Clicking a dot points the camera at that barnacle.
+
+use super::super::focus::FocusOn;
+use super::super::{Barnacle, BarnacleStatus, Whale};
+use super::HUDRelated;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::ui::FocusPolicy;
+
+const MAP_SIZE: f32 = 200.0;
+const DOT_SIZE: f32 = 10.0;
+
+/// The panel the dots are placed on
+#[derive(Component)]
+pub struct Minimap;
+
+/// Marks the barnacle entity it was spawned for
+#[derive(Component)]
+pub struct MinimapDot(Entity);
+
+fn status_color(status: &BarnacleStatus) -> Color {
+    match status {
+        BarnacleStatus::Attaching => Color::rgb(0.9, 0.9, 0.5),
+        BarnacleStatus::Attached => Color::rgb(0.9, 0.1, 0.1),
+        BarnacleStatus::Gone => Color::rgba(0.4, 0.4, 0.4, 0.6),
+    }
+}
+
+pub fn spawn_minimap(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(ImageBundle {
+            style: Style {
+                size: Size::new(Val::Px(MAP_SIZE), Val::Px(MAP_SIZE)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(20.0),
+                    right: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            image: asset_server.load("models/whale.png").into(),
+            color: Color::rgba(1.0, 1.0, 1.0, 0.8).into(),
+            ..Default::default()
+        })
+        // clicks on the map stop here instead of removing the barnacles behind it
+        .insert(Interaction::default())
+        .insert(FocusPolicy::Block)
+        .insert(Minimap)
+        .insert(HUDRelated);
+}
+
+/// Texture coordinates of the point of a mesh surface closest to `point`, both in mesh space.
+/// `None` for a mesh without triangles.
+pub fn surface_uv(
+    positions: &[[f32; 3]],
+    uvs: &[[f32; 2]],
+    triangles: impl IntoIterator<Item = [usize; 3]>,
+    point: Vec3,
+) -> Option<Vec2> {
+    let mut closest: Option<(f32, Vec2)> = None;
+    for [a, b, c] in triangles {
+        let corners = [a, b, c].map(|i| Vec3::from(positions[i]));
+        let weights = closest_point_weights(point, corners);
+        let on_surface = corners[0] * weights.x + corners[1] * weights.y + corners[2] * weights.z;
+        let distance = on_surface.distance_squared(point);
+        if closest.is_none_or(|(best, _)| distance < best) {
+            let uv = Vec2::from(uvs[a]) * weights.x
+                + Vec2::from(uvs[b]) * weights.y
+                + Vec2::from(uvs[c]) * weights.z;
+            closest = Some((distance, uv));
+        }
+    }
+    closest.map(|(_, uv)| uv)
+}
+
+// Barycentric weights of the point of the triangle closest to `p`, from Real-Time Collision
+// Detection by Christer Ericson, 5.1.5
+fn closest_point_weights(p: Vec3, [a, b, c]: [Vec3; 3]) -> Vec3 {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return Vec3::X;
+    }
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return Vec3::Y;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = d1 / (d1 - d3);
+        return Vec3::new(1.0 - v, v, 0.0);
+    }
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return Vec3::Z;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = d2 / (d2 - d6);
+        return Vec3::new(1.0 - w, 0.0, w);
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return Vec3::new(0.0, 1.0 - w, w);
+    }
+    let denom = 1.0 / (va + vb + vc);
+    let v = vb * denom;
+    let w = vc * denom;
+    Vec3::new(1.0 - v - w, v, w)
+}
+
+// `surface_uv` of a loaded mesh, `None` when it has no positions or texture coordinates
+fn mesh_uv(mesh: &Mesh, point: Vec3) -> Option<Vec2> {
+    let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION)? {
+        VertexAttributeValues::Float32x3(positions) => positions,
+        _ => return None,
+    };
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0)? {
+        VertexAttributeValues::Float32x2(uvs) => uvs,
+        _ => return None,
+    };
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        Some(Indices::U32(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        None => (0..positions.len()).collect(),
+    };
+    let triangles = indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]);
+    surface_uv(positions, uvs, triangles, point)
+}
+
+// Adds a dot for every new barnacle once the whale's mesh is loaded, and keeps the dot colors
+// in line with the barnacle status
+#[allow(clippy::type_complexity)]
+pub fn update_minimap_dots(
+    mut commands: Commands,
+    meshes: Res<Assets<Mesh>>,
+    map_query: Query<Entity, With<Minimap>>,
+    whale_query: Query<(&Handle<Mesh>, &GlobalTransform), With<Whale>>,
+    barnacle_query: Query<(Entity, &Barnacle, &GlobalTransform)>,
+    mut dot_query: Query<(&MinimapDot, &mut UiColor)>,
+) {
+    let map = match map_query.iter().next() {
+        Some(map) => map,
+        None => return,
+    };
+    let mut placed = Vec::new();
+    for (MinimapDot(barnacle), mut color) in dot_query.iter_mut() {
+        placed.push(*barnacle);
+        if let Ok((_, barnacle, _)) = barnacle_query.get(*barnacle) {
+            let status = status_color(&barnacle.status);
+            if color.0 != status {
+                color.0 = status;
+            }
+        }
+    }
+
+    let (mesh, world_to_mesh) = match whale_query.iter().next().and_then(|(mesh, transform)| {
+        Some((meshes.get(mesh)?, transform.compute_matrix().inverse()))
+    }) {
+        Some(whale) => whale,
+        None => return,
+    };
+    for (entity, barnacle, transform) in barnacle_query.iter() {
+        if placed.contains(&entity) {
+            continue;
+        }
+        let uv = match mesh_uv(mesh, world_to_mesh.transform_point3(transform.translation)) {
+            Some(uv) => uv,
+            None => continue,
+        };
+        // the texture's top left corner is (0, 0)
+        let position = uv.clamp(Vec2::ZERO, Vec2::ONE) * MAP_SIZE - Vec2::splat(DOT_SIZE / 2.0);
+        // not a `Button`, the menus color those
+        let dot = commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(DOT_SIZE), Val::Px(DOT_SIZE)),
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        left: Val::Px(position.x),
+                        top: Val::Px(position.y),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                color: status_color(&barnacle.status).into(),
+                ..Default::default()
+            })
+            .insert(Interaction::default())
+            .insert(MinimapDot(entity))
+            .id();
+        commands.entity(map).add_child(dot);
+    }
+}
+
+pub fn click_minimap_dot(
+    mut requests: EventWriter<FocusOn>,
+    dot_query: Query<(&Interaction, &MinimapDot), Changed<Interaction>>,
+) {
+    for (interaction, MinimapDot(barnacle)) in dot_query.iter() {
+        if *interaction == Interaction::Clicked {
+            requests.send(FocusOn(*barnacle));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A unit square in the XY plane mapped onto the whole texture, the top of the square at the
+    // top of the texture
+    const POSITIONS: [[f32; 3]; 4] = [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+    ];
+    const UVS: [[f32; 2]; 4] = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+    const TRIANGLES: [[usize; 3]; 2] = [[0, 1, 2], [0, 2, 3]];
+
+    fn assert_uv(point: Vec3, expected: Vec2) {
+        let uv = surface_uv(&POSITIONS, &UVS, TRIANGLES, point).unwrap();
+        assert!(
+            (uv - expected).length() < 1e-5,
+            "{:?} != {:?}",
+            uv,
+            expected
+        );
+    }
+
+    #[test]
+    fn corners_map_to_their_texture_coordinates() {
+        for (position, uv) in POSITIONS.iter().zip(UVS.iter()) {
+            assert_uv(Vec3::from(*position), Vec2::from(*uv));
+        }
+    }
+
+    #[test]
+    fn points_inside_a_triangle_are_interpolated() {
+        assert_uv(Vec3::new(0.25, 0.75, 0.0), Vec2::new(0.25, 0.25));
+        assert_uv(Vec3::new(0.8, 0.1, 0.0), Vec2::new(0.8, 0.9));
+    }
+
+    #[test]
+    fn points_off_the_surface_use_the_closest_surface_point() {
+        // above the surface
+        assert_uv(Vec3::new(0.5, 0.5, 3.0), Vec2::new(0.5, 0.5));
+        // beyond an edge and beyond a corner
+        assert_uv(Vec3::new(2.0, 0.5, 0.0), Vec2::new(1.0, 0.5));
+        assert_uv(Vec3::new(-1.0, -1.0, 1.0), Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn the_closest_triangle_wins() {
+        // a second square far away with other texture coordinates
+        let mut positions = POSITIONS.to_vec();
+        positions.extend(POSITIONS.iter().map(|[x, y, z]| [*x, *y, z + 10.0]));
+        let mut uvs = UVS.to_vec();
+        uvs.extend([[0.5, 0.5]; 4]);
+        let triangles = [[0, 1, 2], [0, 2, 3], [4, 5, 6], [4, 6, 7]];
+        let uv = surface_uv(&positions, &uvs, triangles, Vec3::new(0.5, 0.5, 9.0)).unwrap();
+        assert!((uv - Vec2::splat(0.5)).length() < 1e-5);
+        let uv = surface_uv(&positions, &uvs, triangles, Vec3::new(0.9, 0.5, 1.0)).unwrap();
+        assert!((uv - Vec2::new(0.9, 0.5)).length() < 1e-5);
+    }
+
+    #[test]
+    fn no_triangles_no_uv() {
+        assert_eq!(surface_uv(&POSITIONS, &UVS, [], Vec3::ZERO), None);
+    }
+}
@@ -0,0 +1,189 @@
+mod arrows;
+mod gauge;
+mod minimap;
+mod progress;
+mod sight;
+mod toast;
+
+use super::super::locale::Localization;
+use super::super::settings::Settings;
+use super::super::GameState;
+use super::clock::{clock_text, delta_text, PersonalBests};
+use super::{BarnacleCount, Run};
+use bevy::prelude::*;
+use fluent::FluentArgs;
+
+pub use toast::{Toast, ToastPriority};
+
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.5);
+
+pub struct GameHUDPlugin;
+
+#[derive(Component)]
+struct HUDRelated;
+
+#[derive(Component)]
+struct CountRelated;
+
+#[derive(Component)]
+struct TimerRelated;
+
+// Color of the personal best delta once the run outlasts the best
+const AHEAD_COLOR: Color = Color::rgb(0.4, 0.9, 0.4);
+
+impl Plugin for GameHUDPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Toast>()
+            .init_resource::<gauge::InfestationHistory>()
+            .init_resource::<toast::ToastQueue>()
+            .init_resource::<sight::Sightings>()
+            .add_startup_system(arrows::load_arrow_image)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Game)
+                    .with_system(setup_hud)
+                    .with_system(gauge::spawn_gauge)
+                    .with_system(gauge::reset_history)
+                    .with_system(toast::reset_toasts)
+                    .with_system(minimap::spawn_minimap),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(update_timer_text)
+                    .with_system(sight::sight_barnacles.label("sight_barnacles"))
+                    .with_system(arrows::update_edge_arrows.after("sight_barnacles"))
+                    .with_system(minimap::update_minimap_dots)
+                    .with_system(minimap::click_minimap_dot)
+                    .with_system(update_barnacle_count_text)
+                    .with_system(gauge::update_gauge)
+                    .with_system(progress::update_progress_bars.after("sight_barnacles"))
+                    .with_system(toast::queue_toasts.label("queue_toasts"))
+                    .with_system(toast::show_toasts.after("queue_toasts")),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Game)
+                    .with_system(super::despawn_screen::<HUDRelated>),
+            );
+    }
+}
+
+// The run clock, and below it how far the run is from the personal best of its mode
+fn update_timer_text(
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    run: Res<Run>,
+    bests: Res<PersonalBests>,
+    mut query: Query<&mut Text, With<TimerRelated>>,
+) {
+    let mut args = FluentArgs::new();
+    args.set("time", clock_text(run.elapsed));
+    let time = localization.format(settings.language, "hud-timer", Some(&args));
+    let (delta, color) = match bests.0.get(&run.mode) {
+        Some(best) => {
+            let delta = run.elapsed - best.time;
+            let mut args = FluentArgs::new();
+            args.set("delta", delta_text(delta));
+            (
+                localization.format(settings.language, "hud-best-delta", Some(&args)),
+                if delta >= 0.0 {
+                    AHEAD_COLOR
+                } else {
+                    TEXT_COLOR
+                },
+            )
+        }
+        None => (String::new(), TEXT_COLOR),
+    };
+    for mut text in query.iter_mut() {
+        text.sections[0].value = time.clone();
+        text.sections[1].value = format!("\n{}", delta);
+        text.sections[1].style.color = color;
+    }
+}
+
+fn update_barnacle_count_text(
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    barnacle_count: Res<BarnacleCount>,
+    mut query: Query<&mut Text, With<CountRelated>>,
+) {
+    for mut text in query.iter_mut() {
+        let mut args = FluentArgs::new();
+        args.set("count", barnacle_count.count);
+        text.sections[0].value =
+            localization.format(settings.language, "hud-barnacles", Some(&args));
+    }
+}
+
+fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let timer_text_style = TextStyle {
+        font: asset_server.load("fonts/Kenney Future.ttf"),
+        font_size: 40.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(HUDRelated);
+
+    let delta_text_style = TextStyle {
+        font_size: 24.0,
+        ..timer_text_style.clone()
+    };
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: clock_text(0.0),
+                        style: timer_text_style,
+                    },
+                    TextSection {
+                        value: String::new(),
+                        style: delta_text_style,
+                    },
+                ],
+                ..Default::default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Percent(5.0),
+                    left: Val::Percent(5.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(TimerRelated)
+        .insert(HUDRelated);
+
+    let count_text_style = TextStyle {
+        font: asset_server.load("fonts/Kenney Future.ttf"),
+        font_size: 40.0,
+        color: TEXT_COLOR,
+    };
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text::with_section(
+                "-1",
+                count_text_style,
+                TextAlignment {
+                    vertical: VerticalAlign::Top,
+                    horizontal: HorizontalAlign::Right,
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Percent(5.0),
+                    right: Val::Percent(5.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(HUDRelated)
+        .insert(CountRelated);
+}
@@ -0,0 +1,107 @@
+// Bars above the attaching barnacles filling up as they attach
+
+use super::super::{Barnacle, BarnacleAttachedTimer, BarnacleStatus};
+use super::arrows::urgency_color;
+use super::sight::Sightings;
+use super::HUDRelated;
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+const BAR_WIDTH: f32 = 40.0;
+const BAR_HEIGHT: f32 = 6.0;
+// Pixels between the barnacle and the bottom of its bar
+const BAR_OFFSET: f32 = 16.0;
+
+/// Shows the attaching progress of the barnacle entity it was spawned for
+#[derive(Component)]
+pub struct ProgressBar(Entity);
+
+/// The part of a `ProgressBar` growing with the progress
+#[derive(Component)]
+pub struct ProgressFill;
+
+fn spawn_bar(commands: &mut Commands, barnacle: Entity) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(BAR_WIDTH), Val::Px(BAR_HEIGHT)),
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            color: Color::rgba(0.1, 0.1, 0.1, 0.7).into(),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(ProgressBar(barnacle))
+        .insert(HUDRelated)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                        ..Default::default()
+                    },
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .insert(ProgressFill);
+        });
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_progress_bars(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    sightings: Res<Sightings>,
+    barnacle_query: Query<(Entity, &Barnacle, &BarnacleAttachedTimer)>,
+    mut bar_query: Query<(Entity, &ProgressBar, &Children, &mut Style, &mut Visibility)>,
+    mut fill_query: Query<
+        (&mut Style, &mut UiColor, &mut Visibility),
+        (With<ProgressFill>, Without<ProgressBar>),
+    >,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => Vec2::new(window.width(), window.height()),
+        None => return,
+    };
+    let mut tracked = HashSet::new();
+    for (bar, ProgressBar(barnacle), children, mut style, mut visibility) in bar_query.iter_mut() {
+        let timer = match barnacle_query.get(*barnacle) {
+            Ok((_, b, timer)) if b.status == BarnacleStatus::Attaching => timer,
+            _ => {
+                commands.entity(bar).despawn_recursive();
+                continue;
+            }
+        };
+        tracked.insert(*barnacle);
+        let sighting = sightings.0.get(barnacle);
+        let shown = sighting.is_some_and(|s| s.on_screen() && !s.occluded);
+        if visibility.is_visible != shown {
+            visibility.is_visible = shown;
+        }
+        let progress = timer.0.percent();
+        for child in children.iter() {
+            if let Ok((mut fill_style, mut color, mut fill_visibility)) = fill_query.get_mut(*child)
+            {
+                fill_visibility.is_visible = shown;
+                fill_style.size.width = Val::Percent(progress * 100.0);
+                color.0 = urgency_color(1.0 - progress);
+            }
+        }
+        if let Some(sighting) = sighting.filter(|_| shown) {
+            let screen = sighting.screen_position(window);
+            style.position = Rect {
+                left: Val::Px(screen.x - BAR_WIDTH / 2.0),
+                bottom: Val::Px(screen.y + BAR_OFFSET),
+                ..Default::default()
+            };
+        }
+    }
+
+    for (entity, barnacle, ..) in barnacle_query.iter() {
+        if barnacle.status == BarnacleStatus::Attaching && !tracked.contains(&entity) {
+            // placed from the next frame on
+            spawn_bar(&mut commands, entity);
+        }
+    }
+}
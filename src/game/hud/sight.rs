@@ -0,0 +1,148 @@
+// Where the attaching barnacles appear through the game camera, worked out once per frame for
+// the HUD elements that follow them
+
+use super::super::{Barnacle, BarnacleStatus, GameCamera, Whale};
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy_mod_raycast::{ray_intersection_over_mesh, Ray3d};
+use std::collections::HashMap;
+
+/// How an attaching barnacle is seen by the game camera
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sighting {
+    /// Normalized device coordinates of the barnacle
+    pub ndc: Vec2,
+    /// The barnacle is behind the camera, its `ndc` then only tell which way to turn to it
+    pub behind: bool,
+    /// The whale is between the camera and the barnacle
+    pub occluded: bool,
+}
+
+impl Sighting {
+    /// In front of the camera and inside the view, whether or not the whale hides it
+    pub fn on_screen(&self) -> bool {
+        !self.behind && self.ndc.x.abs() <= 1.0 && self.ndc.y.abs() <= 1.0
+    }
+
+    /// Position in pixels from the bottom left corner of `window`, for points `on_screen`
+    pub fn screen_position(&self, window: Vec2) -> Vec2 {
+        (self.ndc + Vec2::ONE) / 2.0 * window
+    }
+}
+
+/// The `Sighting` of every attaching barnacle this frame
+#[derive(Default)]
+pub struct Sightings(pub HashMap<Entity, Sighting>);
+
+/// Normalized device coordinates of `position` seen through `view_projection`, and whether it
+/// is behind the camera. Points behind keep the side they are on instead of flipping over.
+pub fn project(view_projection: Mat4, position: Vec3) -> (Vec2, bool) {
+    let clip = view_projection * position.extend(1.0);
+    let behind = clip.w <= 0.0;
+    (
+        clip.truncate().truncate() / clip.w.abs().max(f32::EPSILON),
+        behind,
+    )
+}
+
+/// Whether the whale's `mesh`, placed by `mesh_to_world`, is between `eye` and `position`
+pub fn hidden_by_whale(mesh: &Mesh, mesh_to_world: &Mat4, eye: Vec3, position: Vec3) -> bool {
+    let distance = eye.distance(position);
+    let ray = Ray3d::new(eye, (position - eye) / distance);
+    ray_intersection_over_mesh(mesh, mesh_to_world, &ray)
+        .is_some_and(|hit| hit.distance() < distance)
+}
+
+// Casting a ray against the whale is the expensive part, so it is done once per barnacle here
+// and not by every HUD element showing it
+pub fn sight_barnacles(
+    meshes: Res<Assets<Mesh>>,
+    mut sightings: ResMut<Sightings>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    whale_query: Query<(&Handle<Mesh>, &GlobalTransform), With<Whale>>,
+    barnacle_query: Query<(Entity, &Barnacle, &GlobalTransform)>,
+) {
+    sightings.0.clear();
+    let (camera, camera_transform) = match camera_query.iter().next() {
+        Some(camera) => camera,
+        None => return,
+    };
+    let view_projection = camera.projection_matrix * camera_transform.compute_matrix().inverse();
+    let eye = camera_transform.translation;
+    let whale = whale_query
+        .iter()
+        .next()
+        .and_then(|(mesh, transform)| Some((meshes.get(mesh)?, transform.compute_matrix())));
+
+    for (entity, barnacle, transform) in barnacle_query.iter() {
+        if barnacle.status != BarnacleStatus::Attaching {
+            continue;
+        }
+        let position = transform.translation;
+        let (ndc, behind) = project(view_projection, position);
+        let occluded = !behind
+            && whale.as_ref().is_some_and(|(mesh, mesh_to_world)| {
+                hidden_by_whale(mesh, mesh_to_world, eye, position)
+            });
+        sightings.0.insert(
+            entity,
+            Sighting {
+                ndc,
+                behind,
+                occluded,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A camera at the origin looking down -Z
+    fn view_projection() -> Mat4 {
+        Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0)
+    }
+
+    #[test]
+    fn points_ahead_project_into_the_view() {
+        let (ndc, behind) = project(view_projection(), Vec3::new(0.0, 0.0, -5.0));
+        assert!(!behind);
+        assert!(ndc.abs_diff_eq(Vec2::ZERO, 1e-6));
+
+        let (ndc, behind) = project(view_projection(), Vec3::new(5.0, 2.5, -5.0));
+        assert!(!behind);
+        assert!(ndc.abs_diff_eq(Vec2::new(1.0, 0.5), 1e-5));
+    }
+
+    #[test]
+    fn points_behind_keep_their_side() {
+        let (ndc, behind) = project(view_projection(), Vec3::new(5.0, 2.0, 5.0));
+        assert!(behind);
+        assert!(ndc.x > 0.0 && ndc.y > 0.0, "{:?}", ndc);
+    }
+
+    #[test]
+    fn points_on_the_eye_plane_stay_finite() {
+        let (ndc, behind) = project(view_projection(), Vec3::new(1.0, 0.0, 0.0));
+        assert!(behind);
+        assert!(ndc.is_finite());
+    }
+
+    #[test]
+    fn sightings_know_when_they_are_on_screen() {
+        let sighting = |ndc, behind| Sighting {
+            ndc,
+            behind,
+            occluded: false,
+        };
+        assert!(sighting(Vec2::ZERO, false).on_screen());
+        assert!(sighting(Vec2::ONE, false).on_screen());
+        assert!(!sighting(Vec2::ZERO, true).on_screen());
+        assert!(!sighting(Vec2::new(1.5, 0.0), false).on_screen());
+        assert_eq!(
+            sighting(Vec2::ZERO, false).screen_position(Vec2::new(800.0, 600.0)),
+            Vec2::new(400.0, 300.0)
+        );
+    }
+}
@@ -0,0 +1,509 @@
+// Short messages stacked at the top of the screen, like "Barnacle attached!". Any system can
+// show one by sending a `Toast` event.
+
+use super::super::super::locale::Localization;
+use super::super::super::settings::Settings;
+use super::{HUDRelated, TEXT_COLOR};
+use bevy::prelude::*;
+use fluent::FluentArgs;
+
+// Toasts on the screen at once, the others wait for a free slot
+const MAX_SHOWN: usize = 3;
+// Toasts waiting at once, the least important are dropped beyond it
+const MAX_WAITING: usize = 8;
+const FADE_IN_SECONDS: f32 = 0.2;
+const FADE_OUT_SECONDS: f32 = 0.4;
+// Pixels from the top of the window to the first toast, and between toasts
+const TOP: f32 = 80.0;
+const SPACING: f32 = 44.0;
+
+/// Toasts of a higher priority are shown first and push out lower ones when the screen is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ToastPriority {
+    Low,
+    Normal,
+    High,
+}
+
+impl ToastPriority {
+    fn seconds(&self) -> f32 {
+        match self {
+            ToastPriority::Low => 2.0,
+            ToastPriority::Normal => 3.0,
+            ToastPriority::High => 4.0,
+        }
+    }
+}
+
+/// Translated when the toast is drawn, so a toast on the screen follows a change of language
+#[derive(Clone, Debug, PartialEq)]
+pub enum ToastMessage {
+    /// Translation key and its arguments
+    Key(&'static str, Vec<(&'static str, ToastArg)>),
+    /// Translation key taking a `$count`. Toasts with the same key are merged into one, counting
+    /// how often it was sent.
+    Count(&'static str, u32),
+}
+
+/// Value of a translation argument. Numbers stay numbers, so the translation can pick a plural.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ToastArg {
+    Number(u32),
+    Text(String),
+}
+
+impl From<u32> for ToastArg {
+    fn from(number: u32) -> Self {
+        ToastArg::Number(number)
+    }
+}
+
+impl From<String> for ToastArg {
+    fn from(text: String) -> Self {
+        ToastArg::Text(text)
+    }
+}
+
+/// Event showing a message for `duration` seconds, fades included. A toast still waiting for a
+/// slot after that is dropped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toast {
+    pub message: ToastMessage,
+    pub priority: ToastPriority,
+    pub duration: f32,
+}
+
+impl Toast {
+    /// A toast translating `key`, shown for the usual duration of its priority
+    pub fn new(priority: ToastPriority, key: &'static str) -> Self {
+        Toast {
+            message: ToastMessage::Key(key, Vec::new()),
+            priority,
+            duration: priority.seconds(),
+        }
+    }
+
+    /// Adds an argument to the translation of a toast made with `new`
+    pub fn with_arg(mut self, name: &'static str, value: impl Into<ToastArg>) -> Self {
+        if let ToastMessage::Key(_, args) = &mut self.message {
+            args.push((name, value.into()));
+        }
+        self
+    }
+
+    /// A toast counting how often `key` was sent while it is up
+    pub fn counted(priority: ToastPriority, key: &'static str) -> Self {
+        Toast {
+            message: ToastMessage::Count(key, 1),
+            priority,
+            duration: priority.seconds(),
+        }
+    }
+
+    fn text(&self, localization: &Localization, settings: &Settings) -> String {
+        match &self.message {
+            ToastMessage::Key(key, args) => {
+                let mut fluent_args = FluentArgs::new();
+                for (name, value) in args {
+                    match value {
+                        ToastArg::Number(number) => fluent_args.set(*name, *number),
+                        ToastArg::Text(text) => fluent_args.set(*name, text.clone()),
+                    }
+                }
+                localization.format(settings.language, key, Some(&fluent_args))
+            }
+            ToastMessage::Count(key, count) => {
+                let mut args = FluentArgs::new();
+                args.set("count", *count);
+                localization.format(settings.language, key, Some(&args))
+            }
+        }
+    }
+
+    // Adds the count of `other` when both count the same key
+    fn merge(&mut self, other: &Toast) -> bool {
+        match (&mut self.message, &other.message) {
+            (ToastMessage::Count(key, count), ToastMessage::Count(other_key, other_count))
+                if key == other_key =>
+            {
+                *count += other_count;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A toast on the screen
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShownToast {
+    pub id: u64,
+    pub toast: Toast,
+    /// Seconds since it appeared
+    pub age: f32,
+    /// Seconds after which it's gone, shortened when a more important toast needs its slot
+    pub lifetime: f32,
+    // the lifetime was shortened
+    dismissed: bool,
+}
+
+impl ShownToast {
+    /// Opacity while fading in and out
+    pub fn alpha(&self) -> f32 {
+        let fade_in = self.age / FADE_IN_SECONDS;
+        let fade_out = (self.lifetime - self.age) / FADE_OUT_SECONDS;
+        fade_in.min(fade_out).clamp(0.0, 1.0)
+    }
+
+    fn leaving(&self) -> bool {
+        self.dismissed || self.lifetime - self.age <= FADE_OUT_SECONDS
+    }
+}
+
+// A toast waiting for a slot, and for how many seconds
+struct WaitingToast {
+    id: u64,
+    toast: Toast,
+    waited: f32,
+}
+
+/// The toasts on the screen, most important on top, and the ones waiting for a slot
+#[derive(Default)]
+pub struct ToastQueue {
+    shown: Vec<ShownToast>,
+    waiting: Vec<WaitingToast>,
+    next_id: u64,
+}
+
+impl ToastQueue {
+    pub fn push(&mut self, toast: Toast) {
+        // a count still on the screen goes on counting, and stays up for longer
+        for shown in self.shown.iter_mut() {
+            if !shown.leaving() && shown.toast.merge(&toast) {
+                shown.lifetime = shown.age.max(FADE_IN_SECONDS) + shown.toast.duration;
+                return;
+            }
+        }
+        if self
+            .waiting
+            .iter_mut()
+            .any(|waiting| waiting.toast.merge(&toast))
+        {
+            return;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        // after the waiting toasts of the same priority
+        let index = self
+            .waiting
+            .iter()
+            .position(|waiting| waiting.toast.priority < toast.priority)
+            .unwrap_or(self.waiting.len());
+        self.waiting.insert(
+            index,
+            WaitingToast {
+                id,
+                toast,
+                waited: 0.0,
+            },
+        );
+        self.waiting.truncate(MAX_WAITING);
+    }
+
+    /// Ages the toasts by `seconds` and fills the free slots
+    pub fn update(&mut self, seconds: f32) {
+        for shown in self.shown.iter_mut() {
+            shown.age += seconds;
+        }
+        self.shown.retain(|shown| shown.age < shown.lifetime);
+        // by the time they'd show, they'd be about something long gone
+        for waiting in self.waiting.iter_mut() {
+            waiting.waited += seconds;
+        }
+        self.waiting
+            .retain(|waiting| waiting.waited <= waiting.toast.duration);
+
+        while let Some(WaitingToast { toast: next, .. }) = self.waiting.first() {
+            if self.shown.len() < MAX_SHOWN {
+                let WaitingToast { id, toast, .. } = self.waiting.remove(0);
+                let lifetime = toast.duration.max(FADE_IN_SECONDS + FADE_OUT_SECONDS);
+                let index = self
+                    .shown
+                    .iter()
+                    .position(|shown| shown.toast.priority < toast.priority)
+                    .unwrap_or(self.shown.len());
+                self.shown.insert(
+                    index,
+                    ShownToast {
+                        id,
+                        toast,
+                        age: 0.0,
+                        lifetime,
+                        dismissed: false,
+                    },
+                );
+                continue;
+            }
+            // make room by fading out the least important toast once it has faded in, unless a
+            // slot is about to free up anyway
+            if self.shown.iter().any(ShownToast::leaving) {
+                break;
+            }
+            if let Some(least) = self
+                .shown
+                .iter_mut()
+                .filter(|shown| shown.toast.priority < next.priority)
+                .min_by_key(|shown| shown.toast.priority)
+            {
+                least.lifetime = least
+                    .lifetime
+                    .min(least.age.max(FADE_IN_SECONDS) + FADE_OUT_SECONDS);
+                least.dismissed = true;
+            }
+            break;
+        }
+    }
+
+    /// Toasts on the screen from top to bottom
+    pub fn shown(&self) -> &[ShownToast] {
+        &self.shown
+    }
+}
+
+/// The row of the shown toast with this id, its text centered in it
+#[derive(Component)]
+pub struct ToastRow(u64);
+
+pub fn reset_toasts(mut queue: ResMut<ToastQueue>) {
+    *queue = ToastQueue::default();
+}
+
+pub fn queue_toasts(
+    time: Res<Time>,
+    mut events: EventReader<Toast>,
+    mut queue: ResMut<ToastQueue>,
+) {
+    for toast in events.iter() {
+        queue.push(toast.clone());
+    }
+    queue.update(time.delta_seconds());
+}
+
+fn row_top(slot: usize) -> Val {
+    Val::Px(TOP + slot as f32 * SPACING)
+}
+
+pub fn show_toasts(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    queue: Res<ToastQueue>,
+    mut row_query: Query<(Entity, &ToastRow, &Children, &mut Style)>,
+    mut text_query: Query<&mut Text>,
+) {
+    let mut placed = Vec::new();
+    for (entity, ToastRow(id), children, mut style) in row_query.iter_mut() {
+        let slot = match queue.shown().iter().position(|shown| shown.id == *id) {
+            Some(slot) => slot,
+            None => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+        placed.push(*id);
+        if style.position.top != row_top(slot) {
+            style.position.top = row_top(slot);
+        }
+        let shown = &queue.shown()[slot];
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                // counts can go up while shown
+                let value = shown.toast.text(&localization, &settings);
+                if text.sections[0].value != value {
+                    text.sections[0].value = value;
+                }
+                text.sections[0].style.color.set_a(shown.alpha());
+            }
+        }
+    }
+
+    for (slot, shown) in queue.shown().iter().enumerate() {
+        if placed.contains(&shown.id) {
+            continue;
+        }
+        let mut color = TEXT_COLOR;
+        color.set_a(shown.alpha());
+        let style = TextStyle {
+            font: asset_server.load("fonts/Kenney Future.ttf"),
+            font_size: 32.0,
+            color,
+        };
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Auto),
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        top: row_top(slot),
+                        ..Default::default()
+                    },
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
+                ..Default::default()
+            })
+            .insert(ToastRow(shown.id))
+            .insert(HUDRelated)
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        shown.toast.text(&localization, &settings),
+                        style,
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                });
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::super::locale::Language;
+    use super::*;
+
+    fn texts(queue: &ToastQueue) -> Vec<&str> {
+        queue
+            .shown()
+            .iter()
+            .map(|shown| match &shown.toast.message {
+                ToastMessage::Key(key, _) | ToastMessage::Count(key, _) => *key,
+            })
+            .collect()
+    }
+
+    fn counts(queue: &ToastQueue) -> Vec<u32> {
+        queue
+            .shown()
+            .iter()
+            .filter_map(|shown| match shown.toast.message {
+                ToastMessage::Count(_, count) => Some(count),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn toasts_fade_in_and_out_and_expire() {
+        let mut queue = ToastQueue::default();
+        queue.push(Toast::new(ToastPriority::Normal, "Wave 3!"));
+        queue.update(0.0);
+        assert_eq!(queue.shown()[0].alpha(), 0.0);
+        queue.update(FADE_IN_SECONDS / 2.0);
+        assert!((queue.shown()[0].alpha() - 0.5).abs() < 1e-5);
+        queue.update(1.0);
+        assert_eq!(queue.shown()[0].alpha(), 1.0);
+        queue.update(ToastPriority::Normal.seconds() - 1.0 - FADE_IN_SECONDS);
+        assert!(queue.shown()[0].alpha() < 1.0);
+        queue.update(FADE_OUT_SECONDS);
+        assert!(queue.shown().is_empty());
+    }
+
+    #[test]
+    fn toasts_stack_by_priority() {
+        let mut queue = ToastQueue::default();
+        queue.push(Toast::new(ToastPriority::Low, "low"));
+        queue.push(Toast::new(ToastPriority::High, "high"));
+        queue.push(Toast::new(ToastPriority::Normal, "first"));
+        queue.push(Toast::new(ToastPriority::Normal, "second"));
+        queue.update(0.0);
+        assert_eq!(texts(&queue), ["high", "first", "second"]);
+    }
+
+    #[test]
+    fn toasts_wait_for_a_free_slot() {
+        let mut queue = ToastQueue::default();
+        for text in ["a", "b", "c", "d"] {
+            queue.push(Toast::new(ToastPriority::Normal, text));
+        }
+        queue.update(0.0);
+        assert_eq!(texts(&queue), ["a", "b", "c"]);
+        queue.update(ToastPriority::Normal.seconds());
+        assert_eq!(texts(&queue), ["d"]);
+    }
+
+    #[test]
+    fn important_toasts_push_out_less_important_ones() {
+        let mut queue = ToastQueue::default();
+        for text in ["a", "b", "c"] {
+            queue.push(Toast::new(ToastPriority::Low, text));
+        }
+        queue.update(0.0);
+        queue.push(Toast::new(ToastPriority::High, "urgent"));
+        queue.update(0.0);
+        queue.update(0.0);
+        // only one of the low toasts fades out early, once it has faded in
+        let leaving: Vec<_> = queue.shown().iter().filter(|s| s.leaving()).collect();
+        assert_eq!(leaving.len(), 1);
+        assert_eq!(leaving[0].lifetime, FADE_IN_SECONDS + FADE_OUT_SECONDS);
+        queue.update(FADE_IN_SECONDS + FADE_OUT_SECONDS);
+        assert_eq!(texts(&queue)[0], "urgent");
+        assert_eq!(queue.shown().len(), MAX_SHOWN);
+    }
+
+    #[test]
+    fn counted_toasts_merge() {
+        let mut queue = ToastQueue::default();
+        // merged while waiting
+        queue.push(Toast::counted(ToastPriority::Low, "attached"));
+        queue.push(Toast::counted(ToastPriority::Low, "attached"));
+        queue.update(0.0);
+        assert_eq!(counts(&queue), [2]);
+        // and while shown, which keeps it up for longer
+        queue.update(1.5);
+        queue.push(Toast::counted(ToastPriority::Low, "attached"));
+        queue.update(1.5);
+        assert_eq!(counts(&queue), [3]);
+        assert_eq!(queue.shown().len(), 1);
+    }
+
+    #[test]
+    fn stale_toasts_are_dropped() {
+        let mut queue = ToastQueue::default();
+        for text in ["a", "b", "c"] {
+            queue.push(Toast::new(ToastPriority::High, text));
+        }
+        queue.update(0.0);
+        queue.push(Toast::new(ToastPriority::Low, "stale"));
+        // the low toast outlives its own duration waiting behind the high ones
+        queue.update(ToastPriority::Low.seconds() + 0.1);
+        queue.update(ToastPriority::High.seconds());
+        assert!(!texts(&queue).contains(&"stale"));
+    }
+
+    #[test]
+    fn toasts_are_translated_when_shown() {
+        let localization = Localization::default();
+        let mut settings = Settings::default();
+        let toast = Toast::new(ToastPriority::Normal, "toast-wave")
+            .with_arg("wave", 3)
+            .with_arg("time", "00:42.00".to_string());
+        assert_eq!(toast.text(&localization, &settings), "Wave 3! 00:42.00");
+        settings.language = Language::German;
+        assert_eq!(toast.text(&localization, &settings), "Welle 3! 00:42.00");
+    }
+
+    #[test]
+    fn waiting_toasts_are_capped() {
+        let mut queue = ToastQueue::default();
+        for i in 0..100 {
+            queue.push(Toast::new(ToastPriority::Normal, "toast").with_arg("i", i));
+        }
+        queue.push(Toast::new(ToastPriority::High, "urgent"));
+        assert_eq!(queue.waiting.len(), MAX_WAITING);
+        assert_eq!(queue.waiting[0].toast.priority, ToastPriority::High);
+    }
+}
@@ -1,10 +1,30 @@
 mod camera;
+mod clock;
+#[cfg(feature = "dev")]
+mod debug;
+mod focus;
+mod gamepad;
 mod hud;
+mod rear_view;
+mod replay;
+mod touch;
+mod viewpoints;
 
+use super::actions::Action;
+use super::settings::{Difficulty, Settings};
 use super::GameState;
+use bevy::app::AppExit;
+use bevy::core::FixedTimestep;
+use bevy::ecs::schedule::ShouldRun;
 use bevy::prelude::*;
 use bevy_mod_picking::*;
-use rand::Rng;
+use hud::{Toast, ToastPriority};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use replay::{Recording, Removal};
+use std::time::Duration;
+
+pub use replay::{load as load_replay, Playback};
 
 #[derive(Default, Clone)]
 pub struct BarnacleAttachingMaterials {
@@ -17,8 +37,82 @@ pub struct BarnacleCount {
     pub count: u32,
 }
 
+/// Run parameters forced from the command line, instead of a random seed and the
+/// difficulty from the settings
+#[derive(Default)]
+pub struct RunOptions {
+    pub seed: Option<u64>,
+    pub mode: Option<Difficulty>,
+    /// Quit once the whale is overrun, for headless runs nobody plays
+    pub exit_when_overrun: bool,
+}
+
+/// The run in progress
+#[derive(Default)]
+pub struct Run {
+    pub seed: u64,
+    pub mode: Difficulty,
+    /// Simulation steps of the run so far, which replays are indexed by
+    pub frame: u32,
+    /// Seconds played in the run, standing still while it is paused. The HUD shows it as the
+    /// run clock.
+    pub elapsed: f32,
+    /// Number of barnacles spawned so far, used as the id of the next one
+    pub spawned: u32,
+    /// Times at which every wave after the first began
+    pub splits: Vec<f32>,
+    /// Set by the step that overran the whale, the state only changes in the next frame
+    pub overrun: bool,
+}
+
+/// Barnacles spawned in every wave
+const BARNACLES_PER_WAVE: u32 = 10;
+
+impl Run {
+    /// The wave the spawns are in, from 1
+    pub fn wave(&self) -> u32 {
+        self.spawned / BARNACLES_PER_WAVE + 1
+    }
+
+    /// Records the current time as the start of a wave when the spawns entered one since the
+    /// last call, and returns that wave
+    pub fn split_wave(&mut self) -> Option<u32> {
+        let wave = self.wave();
+        if wave as usize <= self.splits.len() + 1 {
+            return None;
+        }
+        self.splits.push(self.elapsed);
+        Some(wave)
+    }
+}
+
+/// Random numbers of the run, seeded from `Run::seed` so runs can be replayed
+pub struct GameRng(StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(StdRng::seed_from_u64(0))
+    }
+}
+
+/// Rate of the simulation steps, independent of the frame rate
+const STEPS_PER_SECOND: f64 = 60.0;
+
+fn step_duration() -> Duration {
+    Duration::from_secs_f64(1.0 / STEPS_PER_SECOND)
+}
+
+/// Stage simulating the run in fixed steps, after `CoreStage::Update` which handles the input.
+/// Barnacles spawn, attach and overrun the whale only here, so a replay of the removals at the
+/// same steps reproduces the run exactly, whatever the frame rate.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+struct GameStep;
+
 pub struct GamePlugin;
 
+/// Model of the whale, also the key of what is stored per whale
+pub const WHALE_MODEL: &str = "models/whale.obj";
+
 #[derive(Component)]
 pub struct BarnacleSpawnTimer(Timer);
 
@@ -32,33 +126,112 @@ impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(BarnacleCount { count: 0 })
             .insert_resource(BarnacleAttachingMaterials::default())
+            .init_resource::<RunOptions>()
+            .init_resource::<Run>()
+            .init_resource::<GameRng>()
+            .init_resource::<Recording>()
+            .init_resource::<Playback>()
+            .init_resource::<camera::CameraLimits>()
+            .init_resource::<focus::BarnacleFocus>()
+            .add_event::<focus::FocusOn>()
+            .init_resource::<touch::GestureRecognizer>()
+            .init_resource::<touch::TouchGestures>()
             .add_startup_system(setup_attaching_material)
+            .add_startup_system(clock::load_personal_bests)
             //.add_startup_system(camera::spawn_camera)
             .add_plugin(hud::GameHUDPlugin)
+            .add_plugin(rear_view::RearViewPlugin)
             .add_plugins(DefaultPickingPlugins)
-            .add_plugin(DebugCursorPickingPlugin) // <- Adds the green debug cursor.
-            .add_plugin(DebugEventsPickingPlugin)
+            .add_stage_after(
+                CoreStage::Update,
+                GameStep,
+                SystemStage::parallel()
+                    .with_run_criteria(FixedTimestep::steps_per_second(STEPS_PER_SECOND)),
+            )
             .add_system_set(
                 SystemSet::on_enter(GameState::Game)
-                    .with_system(setup_game)
-                    .with_system(camera::spawn_camera),
+                    .with_system(start_run.label("start_run"))
+                    .with_system(setup_game.after("start_run"))
+                    .with_system(camera::reset_camera_limits)
+                    .with_system(focus::reset_barnacle_focus)
+                    .with_system(touch::reset_gestures)
+                    .with_system(replay::start_recording.after("start_run"))
+                    .with_system(camera::spawn_camera)
+                    .with_system(gamepad::spawn_virtual_cursor),
             )
             .add_system_set(
                 SystemSet::on_update(GameState::Game)
                     .with_system(keyboard_input_system)
-                    .with_system(camera::pan_orbit_camera)
-                    .with_system(barnacle_count)
-                    .with_system(print_events) //.with_system(hit_barnacle_system),
-                    .with_system(update_spawn_timer) //.with_system(hit_barnacle_system),
-                    .with_system(update_attached_timers) //.with_system(hit_barnacle_system),
-                    .with_system(update_attaching_timers) //.with_system(hit_barnacle_system),
-                    .with_system(spawn_barnacle_on_whale) //.with_system(hit_barnacle_system),
-                    .with_system(update_attached_state) //.with_system(hit_barnacle_system),
-                    .with_system(material_attaching_state), //.with_system(hit_barnacle_system),
+                    .with_system(touch::recognize_gestures.label("touch_gestures"))
+                    .with_system(remove_barnacle_system.after("touch_gestures"))
+                    .with_system(camera::update_camera_limits.before("pan_orbit_camera"))
+                    .with_system(
+                        camera::pan_orbit_camera
+                            .label("pan_orbit_camera")
+                            .after("touch_gestures"),
+                    )
+                    .with_system(
+                        focus::focus_barnacle
+                            .label("focus_barnacle")
+                            .after("pan_orbit_camera"),
+                    )
+                    .with_system(
+                        viewpoints::store_and_recall_viewpoints
+                            .label("viewpoints")
+                            .after("pan_orbit_camera"),
+                    )
+                    .with_system(
+                        camera::animate_camera_transition
+                            .label("camera_transition")
+                            .after("focus_barnacle")
+                            .after("viewpoints"),
+                    )
+                    .with_system(
+                        camera::follow_camera_target
+                            .label("follow_camera")
+                            .after("camera_transition"),
+                    )
+                    .with_system(gamepad::move_virtual_cursor),
             )
+            // every step runs in the same order, the parallel executor's order may vary.
+            // State sets only work in the stage driving the state, which is `CoreStage::Update`.
+            .add_system_set_to_stage(
+                GameStep,
+                SystemSet::new()
+                    .with_run_criteria(run_in_progress)
+                    .with_system(replay::play_back.label("play_back"))
+                    .with_system(update_run.label("update_run").after("play_back"))
+                    .with_system(update_spawn_timer.label("tick").after("update_run"))
+                    .with_system(update_attached_timers.label("tick").after("update_run"))
+                    .with_system(update_attaching_timers.label("tick").after("update_run"))
+                    .with_system(spawn_barnacle_on_whale.label("spawn").after("tick"))
+                    .with_system(clock::split_waves.after("spawn"))
+                    .with_system(update_attached_state.label("attach").after("tick"))
+                    .with_system(material_attaching_state.after("tick"))
+                    .with_system(barnacle_count.label("barnacle_count").after("attach"))
+                    .with_system(check_overrun.label("check_overrun").after("barnacle_count"))
+                    .with_system(replay::finish_playback.after("check_overrun")),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(pause_picking))
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(resume_picking))
             .add_system_set(
-                SystemSet::on_exit(GameState::Game).with_system(despawn_screen::<OnGameScreen>),
+                SystemSet::on_enter(GameState::Overrun)
+                    .with_system(pause_picking)
+                    .with_system(exit_when_overrun),
             )
+            .add_system_set(SystemSet::on_exit(GameState::Overrun).with_system(resume_picking))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Game)
+                    .with_system(despawn_screen::<OnGameScreen>)
+                    // before the replay is let go of
+                    .with_system(clock::finish_run.before("save_recording"))
+                    .with_system(replay::save_recording.label("save_recording")),
+            );
+
+        #[cfg(feature = "dev")]
+        app.add_plugin(debug::DebugOverlayPlugin)
+            .add_plugin(DebugCursorPickingPlugin) // <- Adds the green debug cursor.
+            .add_plugin(DebugEventsPickingPlugin)
             .add_system_to_stage(CoreStage::PostUpdate, print_events);
     }
 }
@@ -69,14 +242,20 @@ struct OnGameScreen;
 #[derive(Component)]
 struct GameCamera;
 
+#[derive(Component)]
+pub struct Whale;
+
 #[derive(Component)]
 pub struct Barnacle {
+    /// Spawn order within the run
+    pub id: u32,
     pub status: BarnacleStatus,
 }
 
 impl Barnacle {
-    pub fn new() -> Barnacle {
+    pub fn new(id: u32) -> Barnacle {
         Barnacle {
+            id,
             status: BarnacleStatus::Attaching,
         }
     }
@@ -98,17 +277,57 @@ fn setup_attaching_material(
     attaching_materials.hell3 = materials.add(Color::rgb(0.75, 0.75, 0.75).into());
 }
 
+// Picks the seed and mode of a new run. A replay dictates both.
+fn start_run(
+    settings: Res<Settings>,
+    options: Res<RunOptions>,
+    playback: Res<Playback>,
+    mut run: ResMut<Run>,
+    mut rng: ResMut<GameRng>,
+) {
+    let (seed, mode) = match &playback.replay {
+        Some(replay) => (replay.seed, replay.mode),
+        None => (
+            options.seed.unwrap_or_else(rand::random),
+            options.mode.unwrap_or(settings.difficulty),
+        ),
+    };
+    info!("Starting a {:?} run with seed {}", mode, seed);
+    *run = Run {
+        seed,
+        mode,
+        ..Default::default()
+    };
+    rng.0 = StdRng::seed_from_u64(seed);
+}
+
+// Steps the run while it is played and not overrun
+fn run_in_progress(state: Res<State<GameState>>, run: Res<Run>) -> ShouldRun {
+    if *state.current() == GameState::Game && !run.overrun {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+fn update_run(mut run: ResMut<Run>) {
+    run.frame += 1;
+    run.elapsed = (run.frame as f64 / STEPS_PER_SECOND) as f32;
+}
+
 fn setup_game(
     mut commands: Commands,
     mut materials: ResMut<Assets<StandardMaterial>>,
     asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    run: Res<Run>,
 ) {
     // light
     commands
         .spawn_bundle(PointLightBundle {
             point_light: PointLight {
                 intensity: 1500.0,
-                shadows_enabled: true,
+                shadows_enabled: settings.shadows,
                 ..Default::default()
             },
             transform: Transform::from_xyz(4.0, 8.0, 4.0),
@@ -118,7 +337,7 @@ fn setup_game(
 
     // whale
     // Load OBJ file
-    let whale_mesh_handle = asset_server.load("models/whale.obj");
+    let whale_mesh_handle = asset_server.load(WHALE_MODEL);
     commands
         .spawn_bundle(PbrBundle {
             mesh: whale_mesh_handle,
@@ -129,27 +348,101 @@ fn setup_game(
             transform: Transform::from_xyz(0.0, 0.5, 0.0),
             ..Default::default()
         })
+        .insert(Whale)
         .insert(OnGameScreen);
 
     commands
         .spawn()
         .insert(OnGameScreen)
-        .insert(BarnacleSpawnTimer(Timer::from_seconds(1.0, true)));
+        .insert(BarnacleSpawnTimer(Timer::from_seconds(
+            run.mode.spawn_interval(),
+            true,
+        )));
 }
 
 fn keyboard_input_system(
     mut game_state: ResMut<State<GameState>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    mut actions: ResMut<Input<Action>>,
 ) {
-    if keyboard_input.pressed(KeyCode::Escape) {
-        let _ = game_state.set(GameState::Menu);
+    if actions.just_pressed(Action::Pause) {
+        let _ = game_state.push(GameState::Paused);
+        // the paused state runs in this frame too, and would take the same press as a resume
+        actions.clear_just_pressed(Action::Pause);
     }
 
-    if keyboard_input.pressed(KeyCode::Q) {
+    if actions.just_pressed(Action::Quit) {
         let _ = game_state.set(GameState::Menu);
     }
 }
 
+// Ends the run once enough barnacles are attached
+fn check_overrun(
+    mut run: ResMut<Run>,
+    barnacle_count: Res<BarnacleCount>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if barnacle_count.count >= run.mode.overrun_count() {
+        info!("The whale was overrun after {:.2}s", run.elapsed);
+        run.overrun = true;
+        let _ = game_state.push(GameState::Overrun);
+    }
+}
+
+fn exit_when_overrun(options: Res<RunOptions>, mut exit: EventWriter<AppExit>) {
+    if options.exit_when_overrun {
+        exit.send(AppExit);
+    }
+}
+
+// Picking stops while the pause menu is open, so clicks on its buttons don't reach the
+// barnacles behind them
+fn pause_picking(mut picking: ResMut<PickingPluginsState>) {
+    picking.enable_picking = false;
+    picking.enable_highlighting = false;
+    picking.enable_interacting = false;
+}
+
+fn resume_picking(mut picking: ResMut<PickingPluginsState>, mut actions: ResMut<Input<Action>>) {
+    picking.enable_picking = true;
+    picking.enable_highlighting = true;
+    picking.enable_interacting = true;
+    // the click on the resume button
+    actions.clear_just_pressed(Action::Remove);
+}
+
+// Removes the barnacle under the cursor when the `Remove` action is triggered, or under a
+// finger tapping the screen. The picking ray follows the finger while it touches the screen.
+#[allow(clippy::too_many_arguments)]
+fn remove_barnacle_system(
+    actions: Res<Input<Action>>,
+    touch: Res<touch::TouchGestures>,
+    paused: Res<PausedForBlockers>,
+    run: Res<Run>,
+    playback: Res<Playback>,
+    mut recording: ResMut<Recording>,
+    pick_source_query: Query<&PickingCamera>,
+    mut query: Query<&mut Barnacle>,
+) {
+    // picking is paused while the cursor is over a UI node
+    let triggered = actions.just_pressed(Action::Remove) || touch.tap;
+    if !triggered || paused.is_paused() || playback.is_playing() {
+        return;
+    }
+    for pick_source in pick_source_query.iter() {
+        if let Some((entity, _)) = pick_source.intersect_top() {
+            if let Ok(mut barnacle) = query.get_mut(entity) {
+                if barnacle.status != BarnacleStatus::Gone {
+                    barnacle.status = BarnacleStatus::Gone;
+                    recording.0.removals.push(Removal {
+                        frame: run.frame,
+                        barnacle: barnacle.id,
+                    });
+                }
+            }
+        }
+    }
+}
+
 fn barnacle_count(mut barnacle_count: ResMut<BarnacleCount>, query: Query<&Barnacle>) {
     barnacle_count.count = query
         .iter()
@@ -161,14 +454,17 @@ fn spawn_barnacle_on_whale(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut run: ResMut<Run>,
+    mut rng: ResMut<GameRng>,
     query: Query<&BarnacleSpawnTimer>,
 ) {
     for spawn_timer in query.iter() {
         if spawn_timer.0.just_finished() {
-            let mut rng = rand::thread_rng();
-            let x = rng.gen_range(0.0..1.0);
-            let y = rng.gen_range(0.0..1.0);
-            let z = rng.gen_range(0.0..1.0);
+            let x = rng.0.gen_range(0.0..1.0);
+            let y = rng.0.gen_range(0.0..1.0);
+            let z = rng.0.gen_range(0.0..1.0);
+            let id = run.spawned;
+            run.spawned += 1;
             let barnacle_mesh_handle = asset_server.load("models/barnacle.obj");
             commands
                 .spawn_bundle(PbrBundle {
@@ -178,18 +474,29 @@ fn spawn_barnacle_on_whale(
                     ..Default::default()
                 })
                 .insert(OnGameScreen)
-                .insert(Barnacle::new())
-                .insert(BarnacleAttachedTimer(Timer::from_seconds(5.0, false)))
+                .insert(Barnacle::new(id))
+                .insert(BarnacleAttachedTimer(Timer::from_seconds(
+                    run.mode.attach_seconds(),
+                    false,
+                )))
                 .insert(BarnacleAttachingTimer(Timer::from_seconds(0.1, true)))
                 .insert_bundle(PickableBundle::default());
         }
     }
 }
 
-fn update_attached_state(mut query: Query<(&mut Barnacle, &BarnacleAttachedTimer)>) {
+fn update_attached_state(
+    mut toasts: EventWriter<Toast>,
+    mut query: Query<(&mut Barnacle, &BarnacleAttachedTimer)>,
+) {
     for (mut barnacle, timer) in query.iter_mut() {
-        if timer.0.just_finished() {
+        // removed barnacles keep their timer
+        if timer.0.just_finished() && barnacle.status == BarnacleStatus::Attaching {
             barnacle.status = BarnacleStatus::Attached;
+            toasts.send(Toast::counted(
+                ToastPriority::Low,
+                "toast-barnacle-attached",
+            ));
         }
     }
 }
@@ -210,35 +517,32 @@ fn material_attaching_state(
     }
 }
 
-pub fn print_events(mut events: EventReader<PickingEvent>, mut query: Query<&mut Barnacle>) {
+#[cfg(feature = "dev")]
+pub fn print_events(mut events: EventReader<PickingEvent>) {
     for event in events.iter() {
         match event {
             PickingEvent::Selection(e) => info!("A selection event happened: {:?}", e),
             PickingEvent::Hover(e) => info!("Egads! A hover event!? {:?}", e),
-            PickingEvent::Clicked(e) => {
-                info!("Gee Willikers, it's a click! {:?}", e);
-                let mut barancle = query.get_mut(*e).unwrap();
-                barancle.status = BarnacleStatus::Gone;
-            }
+            PickingEvent::Clicked(e) => info!("Gee Willikers, it's a click! {:?}", e),
         }
     }
 }
 
-fn update_spawn_timer(time: Res<Time>, mut query: Query<&mut BarnacleSpawnTimer>) {
+fn update_spawn_timer(mut query: Query<&mut BarnacleSpawnTimer>) {
     for mut timer in query.iter_mut() {
-        timer.0.tick(time.delta());
+        timer.0.tick(step_duration());
     }
 }
 
-fn update_attaching_timers(time: Res<Time>, mut query: Query<&mut BarnacleAttachingTimer>) {
+fn update_attaching_timers(mut query: Query<&mut BarnacleAttachingTimer>) {
     for mut timer in query.iter_mut() {
-        timer.0.tick(time.delta());
+        timer.0.tick(step_duration());
     }
 }
 
-fn update_attached_timers(time: Res<Time>, mut query: Query<&mut BarnacleAttachedTimer>) {
+fn update_attached_timers(mut query: Query<&mut BarnacleAttachedTimer>) {
     for mut timer in query.iter_mut() {
-        timer.0.tick(time.delta());
+        timer.0.tick(step_duration());
     }
 }
 
@@ -0,0 +1,371 @@
+// The far side of the whale, seen from opposite the game camera, in an inset in the top left
+// corner of the HUD. Bevy 0.6 cameras only render to windows, so the rear view camera is not an
+// active camera: its view is extracted here and drawn into an image, which the inset shows.
+
+use super::super::actions::Action;
+use super::super::settings::Settings;
+use super::super::GameState;
+use super::camera::{outside_distance, PanOrbitCamera, SmoothedCamera};
+use super::{GameCamera, OnGameScreen, Whale};
+use bevy::core_pipeline::{draw_3d_graph, node, AlphaMask3d, Opaque3d, Transparent3d};
+use bevy::prelude::*;
+use bevy::render::camera::{CameraProjection, DepthCalculation};
+use bevy::render::primitives::{Aabb, Frustum};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotValue};
+use bevy::render::render_phase::RenderPhase;
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::texture::{BevyDefault, TextureCache};
+use bevy::render::view::{
+    update_frusta, ExtractedView, ViewTarget, VisibilitySystems, VisibleEntities,
+};
+use bevy::render::{RenderApp, RenderStage};
+use bevy::transform::TransformSystem;
+use bevy::ui::FocusPolicy;
+use std::f32::consts::PI;
+
+const REAR_VIEW_PASS_DRIVER: &str = "rear_view_pass_driver";
+
+/// The image the rear view is drawn into, and the width it was last sized to
+pub struct RearView {
+    image: Handle<Image>,
+    size: u32,
+}
+
+impl FromWorld for RearView {
+    fn from_world(world: &mut World) -> Self {
+        let size = world.get_resource::<Settings>().unwrap().rear_view_size;
+        let mut image = Image::new_fill(
+            extent(size),
+            TextureDimension::D2,
+            &[0, 0, 0, 255],
+            TextureFormat::bevy_default(),
+        );
+        image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_DST
+            | TextureUsages::RENDER_ATTACHMENT;
+        let mut images = world.get_resource_mut::<Assets<Image>>().unwrap();
+        RearView {
+            image: images.add(image),
+            size,
+        }
+    }
+}
+
+// Four by three, `size` pixels wide
+fn extent(size: u32) -> Extent3d {
+    Extent3d {
+        width: size,
+        height: size * 3 / 4,
+        depth_or_array_layers: 1,
+    }
+}
+
+/// Renders the far side of the whale. Without a `PickingCameraBundle`, so nothing is picked
+/// through it.
+#[derive(Component)]
+pub struct RearViewCamera;
+
+/// The HUD node showing the rear view image
+#[derive(Component)]
+pub struct RearViewInset;
+
+/// Perspective with the aspect ratio of the inset. Unlike `PerspectiveProjection` it ignores
+/// window resizes, since it doesn't render to the window.
+#[derive(Component)]
+pub struct RearViewProjection(PerspectiveProjection);
+
+impl CameraProjection for RearViewProjection {
+    fn get_projection_matrix(&self) -> Mat4 {
+        self.0.get_projection_matrix()
+    }
+
+    fn update(&mut self, _width: f32, _height: f32) {}
+
+    fn depth_calculation(&self) -> DepthCalculation {
+        self.0.depth_calculation()
+    }
+
+    fn far(&self) -> f32 {
+        self.0.far()
+    }
+}
+
+/// The camera looking at the focus of `view` from the opposite side
+pub fn rear_of(view: &PanOrbitCamera) -> PanOrbitCamera {
+    PanOrbitCamera {
+        yaw: view.yaw + PI,
+        pitch: -view.pitch,
+        ..*view
+    }
+}
+
+pub struct RearViewPlugin;
+
+impl Plugin for RearViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RearView>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_frusta::<RearViewProjection>
+                    .after(TransformSystem::TransformPropagate)
+                    .before(VisibilitySystems::CheckVisibility),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(spawn_rear_view))
+            .add_system_set(
+                SystemSet::on_update(GameState::Game)
+                    .with_system(toggle_rear_view.label("toggle_rear_view"))
+                    .with_system(show_rear_view.after("toggle_rear_view"))
+                    .with_system(follow_game_camera.after("follow_camera")),
+            );
+
+        // there is no renderer in headless runs
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .add_system_to_stage(RenderStage::Extract, extract_rear_view)
+                // the image is only on the GPU once the prepare stage is over
+                .add_system_to_stage(RenderStage::Queue, prepare_rear_view_target);
+            let rear_view_pass_driver = RearViewPassDriver::new(&mut render_app.world);
+            let mut graph = render_app.world.get_resource_mut::<RenderGraph>().unwrap();
+            graph.add_node(REAR_VIEW_PASS_DRIVER, rear_view_pass_driver);
+            for (from, to) in [
+                (node::CLEAR_PASS_DRIVER, REAR_VIEW_PASS_DRIVER),
+                (node::MAIN_PASS_DEPENDENCIES, REAR_VIEW_PASS_DRIVER),
+                (REAR_VIEW_PASS_DRIVER, node::MAIN_PASS_DRIVER),
+            ] {
+                graph.add_node_edge(from, to).unwrap();
+            }
+        }
+    }
+}
+
+fn spawn_rear_view(mut commands: Commands, settings: Res<Settings>, rear_view: Res<RearView>) {
+    let projection = RearViewProjection(PerspectiveProjection {
+        aspect_ratio: 4.0 / 3.0,
+        ..Default::default()
+    });
+    commands
+        .spawn_bundle((
+            Camera {
+                projection_matrix: projection.get_projection_matrix(),
+                depth_calculation: projection.depth_calculation(),
+                near: projection.0.near,
+                far: projection.far(),
+                ..Default::default()
+            },
+            VisibleEntities::default(),
+            Frustum::default(),
+            Transform::default(),
+            GlobalTransform::default(),
+        ))
+        .insert(projection)
+        .insert(RearViewCamera)
+        .insert(OnGameScreen);
+
+    commands
+        .spawn_bundle(ImageBundle {
+            style: inset_style(&settings),
+            image: rear_view.image.clone().into(),
+            ..Default::default()
+        })
+        // clicks on the inset stop here instead of removing the barnacles behind it
+        .insert(Interaction::default())
+        .insert(FocusPolicy::Block)
+        .insert(RearViewInset)
+        .insert(OnGameScreen);
+}
+
+fn inset_style(settings: &Settings) -> Style {
+    let size = extent(settings.rear_view_size);
+    Style {
+        display: if settings.rear_view {
+            Display::Flex
+        } else {
+            Display::None
+        },
+        size: Size::new(Val::Px(size.width as f32), Val::Px(size.height as f32)),
+        position_type: PositionType::Absolute,
+        position: Rect {
+            top: Val::Px(20.0),
+            left: Val::Px(20.0),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn toggle_rear_view(actions: Res<Input<Action>>, mut settings: ResMut<Settings>) {
+    if actions.just_pressed(Action::RearView) {
+        settings.rear_view = !settings.rear_view;
+    }
+}
+
+// Shows, hides or resizes the inset and its image to match the settings
+fn show_rear_view(
+    settings: Res<Settings>,
+    mut rear_view: ResMut<RearView>,
+    mut images: ResMut<Assets<Image>>,
+    mut inset_query: Query<&mut Style, With<RearViewInset>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut style in inset_query.iter_mut() {
+        *style = inset_style(&settings);
+    }
+    if settings.rear_view_size != rear_view.size {
+        if let Some(image) = images.get_mut(&rear_view.image) {
+            image.resize(extent(settings.rear_view_size));
+        }
+        rear_view.size = settings.rear_view_size;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn follow_game_camera(
+    meshes: Res<Assets<Mesh>>,
+    whale_query: Query<(&Handle<Mesh>, &GlobalTransform, &Aabb), With<Whale>>,
+    game_camera_query: Query<&SmoothedCamera, With<GameCamera>>,
+    mut rear_query: Query<&mut Transform, With<RearViewCamera>>,
+) {
+    let mut view = match game_camera_query.iter().next() {
+        Some(smoothed) => rear_of(&smoothed.current),
+        None => return,
+    };
+    for (mesh, whale_transform, aabb) in whale_query.iter() {
+        if let Some(mesh) = meshes.get(mesh) {
+            view.radius = outside_distance(&view, mesh, whale_transform, aabb);
+        }
+    }
+    let next = view.transform();
+    for mut transform in rear_query.iter_mut() {
+        if *transform != next {
+            *transform = next;
+        }
+    }
+}
+
+/// The image a rear view is drawn into, in the render world
+#[derive(Component)]
+struct RearViewTarget(Handle<Image>);
+
+// Turns the rear view camera into a 3d view of the render world, the way active cameras are
+fn extract_rear_view(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    rear_view: Res<RearView>,
+    camera_query: Query<
+        (Entity, &Camera, &GlobalTransform, &VisibleEntities),
+        With<RearViewCamera>,
+    >,
+) {
+    if !settings.rear_view {
+        return;
+    }
+    let size = extent(rear_view.size);
+    for (entity, camera, transform, visible_entities) in camera_query.iter() {
+        commands.get_or_spawn(entity).insert_bundle((
+            ExtractedView {
+                projection: camera.projection_matrix,
+                transform: *transform,
+                width: size.width,
+                height: size.height,
+                near: camera.near,
+                far: camera.far,
+            },
+            visible_entities.clone(),
+            RearViewTarget(rear_view.image.clone_weak()),
+            RenderPhase::<Opaque3d>::default(),
+            RenderPhase::<AlphaMask3d>::default(),
+            RenderPhase::<Transparent3d>::default(),
+        ));
+    }
+}
+
+// Points the view at its image, with a multisampled texture in between when MSAA is on
+fn prepare_rear_view_target(
+    mut commands: Commands,
+    images: Res<RenderAssets<Image>>,
+    msaa: Res<Msaa>,
+    render_device: Res<RenderDevice>,
+    mut texture_cache: ResMut<TextureCache>,
+    view_query: Query<(Entity, &RearViewTarget, &ExtractedView)>,
+) {
+    for (entity, target, view) in view_query.iter() {
+        let image = match images.get(&target.0) {
+            // a resized image may not be on the GPU yet
+            Some(image) if image.size == Size::new(view.width as f32, view.height as f32) => image,
+            _ => continue,
+        };
+        let sampled_target = (msaa.samples > 1).then(|| {
+            texture_cache
+                .get(
+                    &render_device,
+                    TextureDescriptor {
+                        label: Some("rear_view_sampled_color_attachment_texture"),
+                        size: extent(view.width),
+                        mip_level_count: 1,
+                        sample_count: msaa.samples,
+                        dimension: TextureDimension::D2,
+                        format: TextureFormat::bevy_default(),
+                        usage: TextureUsages::RENDER_ATTACHMENT,
+                    },
+                )
+                .default_view
+        });
+        commands.entity(entity).insert(ViewTarget {
+            view: image.texture_view.clone(),
+            sampled_target,
+        });
+    }
+}
+
+// Draws the 3d scene for the rear view, like the main pass driver does for the game camera.
+// The clear pass has cleared its image already.
+struct RearViewPassDriver {
+    query: QueryState<Entity, (With<RearViewTarget>, With<ViewTarget>)>,
+}
+
+impl RearViewPassDriver {
+    fn new(world: &mut World) -> Self {
+        RearViewPassDriver {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for RearViewPassDriver {
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        _render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        for view in self.query.iter_manual(world) {
+            graph.run_sub_graph(draw_3d_graph::NAME, vec![SlotValue::Entity(view)])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rear_view_looks_at_the_focus_from_the_opposite_side() {
+        let view = PanOrbitCamera::looking_at(Vec3::new(0.5, 0.0, 0.0), Vec3::new(2.0, 1.0, 3.0));
+        let rear = rear_of(&view);
+        assert_eq!(rear.focus, view.focus);
+        assert_eq!(rear.radius, view.radius);
+        let mirrored = view.focus * 2.0 - view.eye();
+        assert!((rear.eye() - mirrored).length() < 1e-4);
+    }
+}
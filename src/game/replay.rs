@@ -0,0 +1,149 @@
+// Records every run and plays recorded runs back. Barnacles spawn from the seeded `GameRng` in
+// the fixed steps of `GameStep`, so a run is reproduced by its seed, its mode and the steps at
+// which the player removed barnacles.
+
+use super::super::settings::{storage, Difficulty};
+use super::{Barnacle, BarnacleStatus, Run};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const REPLAY_KEY: &str = "replay";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub mode: Difficulty,
+    /// Length of the run in simulation steps
+    pub frames: u32,
+    pub removals: Vec<Removal>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Removal {
+    /// Simulation steps of the run before the removal
+    pub frame: u32,
+    /// Spawn order of the removed barnacle
+    pub barnacle: u32,
+}
+
+pub fn load(path: &Path) -> Result<Replay, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    ron::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// The run being recorded
+#[derive(Default)]
+pub struct Recording(pub Replay);
+
+/// The run being played back, if any. Player removals are ignored while it plays.
+#[derive(Default)]
+pub struct Playback {
+    pub replay: Option<Replay>,
+    next: usize,
+    /// Exit the app at the end of the replay instead of handing over to the player
+    pub exit_when_done: bool,
+}
+
+impl Playback {
+    pub fn new(replay: Replay, exit_when_done: bool) -> Self {
+        Playback {
+            replay: Some(replay),
+            next: 0,
+            exit_when_done,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.replay.is_some()
+    }
+}
+
+pub fn start_recording(run: Res<Run>, mut recording: ResMut<Recording>) {
+    recording.0 = Replay {
+        seed: run.seed,
+        mode: run.mode,
+        ..Default::default()
+    };
+}
+
+pub fn save_recording(
+    run: Res<Run>,
+    mut playback: ResMut<Playback>,
+    mut recording: ResMut<Recording>,
+) {
+    // a played back run would only record itself again, and is not resumed by the next run
+    if playback.is_playing() {
+        playback.replay = None;
+        return;
+    }
+    recording.0.frames = run.frame;
+    let result = ron::ser::to_string_pretty(&recording.0, Default::default())
+        .map_err(|e| e.to_string())
+        .and_then(|contents| storage::write(REPLAY_KEY, &contents).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Could not save the replay: {}", e);
+    }
+}
+
+// Makes the removals recorded before this step, where the player made them between steps
+pub fn play_back(run: Res<Run>, mut playback: ResMut<Playback>, mut query: Query<&mut Barnacle>) {
+    let playback = &mut *playback;
+    let replay = match &playback.replay {
+        Some(replay) => replay,
+        None => return,
+    };
+
+    let due = replay.removals[playback.next..]
+        .iter()
+        .take_while(|removal| removal.frame <= run.frame)
+        .count();
+    remove(
+        &replay.removals[playback.next..playback.next + due],
+        &mut query,
+    );
+    playback.next += due;
+}
+
+fn remove(removals: &[Removal], query: &mut Query<&mut Barnacle>) {
+    for removal in removals {
+        for mut barnacle in query.iter_mut() {
+            if barnacle.id == removal.barnacle {
+                barnacle.status = BarnacleStatus::Gone;
+            }
+        }
+    }
+}
+
+// Ends the replay after its last step, also when that step overran the whale
+pub fn finish_playback(
+    run: Res<Run>,
+    mut playback: ResMut<Playback>,
+    mut recording: ResMut<Recording>,
+    mut exit: EventWriter<AppExit>,
+    mut query: Query<&mut Barnacle>,
+) {
+    let replay = match &playback.replay {
+        Some(replay) => replay,
+        None => return,
+    };
+    if run.frame >= replay.frames {
+        // removals after the last step, made before the player quit
+        remove(&replay.removals[playback.next..], &mut query);
+        let attached = query
+            .iter()
+            .filter(|b| b.status == BarnacleStatus::Attached)
+            .count();
+        info!(
+            "Replay finished after {:.2}s with {} barnacles attached",
+            run.elapsed, attached
+        );
+        if playback.exit_when_done {
+            exit.send(AppExit);
+        }
+        // the player takes over, and the recording continues from the replayed removals
+        recording.0.removals = replay.removals.clone();
+        playback.replay = None;
+    }
+}
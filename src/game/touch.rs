@@ -0,0 +1,249 @@
+// Turns raw touch events into the gestures driving the camera and barnacle removal on phones
+// and tablets
+
+use bevy::input::touch::{TouchInput, TouchPhase};
+use bevy::prelude::*;
+
+// A tap is a finger lifted within this many seconds, without moving further than this many
+// pixels
+const TAP_SECONDS: f64 = 0.3;
+const TAP_SLOP: f32 = 10.0;
+
+/// What the fingers did during one frame. Drags are in pixels like `MouseMotion`, with y
+/// growing downwards.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TouchGestures {
+    /// A one finger tap ended. The picking follows the finger, so it picks what was tapped.
+    pub tap: bool,
+    /// One finger drag
+    pub orbit: Vec2,
+    /// Movement of the point between two fingers
+    pub pan: Vec2,
+    /// Change of the distance between two fingers, above 1 when they spread apart
+    pub pinch: f32,
+}
+
+impl Default for TouchGestures {
+    fn default() -> Self {
+        TouchGestures {
+            tap: false,
+            orbit: Vec2::ZERO,
+            pan: Vec2::ZERO,
+            pinch: 1.0,
+        }
+    }
+}
+
+struct Finger {
+    id: u64,
+    start: Vec2,
+    position: Vec2,
+}
+
+/// Follows the fingers on the screen across frames
+#[derive(Default)]
+pub struct GestureRecognizer {
+    fingers: Vec<Finger>,
+    // when the first finger of the current gesture went down
+    started: f64,
+    // a tap is still possible: one finger only, barely moved
+    tap_possible: bool,
+    // more than one finger was down since all were last lifted, single finger drags would make
+    // the camera jump when letting go of a pinch
+    multi_touch: bool,
+}
+
+impl GestureRecognizer {
+    /// Gestures made by the touch `events` of a frame starting `now` seconds into the game
+    pub fn update<'a>(
+        &mut self,
+        events: impl IntoIterator<Item = &'a TouchInput>,
+        now: f64,
+    ) -> TouchGestures {
+        let mut gestures = TouchGestures::default();
+        for event in events {
+            match event.phase {
+                TouchPhase::Started => {
+                    if self.fingers.is_empty() {
+                        self.started = now;
+                        self.tap_possible = true;
+                        self.multi_touch = false;
+                    } else {
+                        self.tap_possible = false;
+                        self.multi_touch = true;
+                    }
+                    self.fingers.retain(|finger| finger.id != event.id);
+                    self.fingers.push(Finger {
+                        id: event.id,
+                        start: event.position,
+                        position: event.position,
+                    });
+                }
+                TouchPhase::Moved => self.moved(event, &mut gestures),
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    let finger = match self.fingers.iter().position(|f| f.id == event.id) {
+                        Some(index) => self.fingers.remove(index),
+                        None => continue,
+                    };
+                    gestures.tap |= event.phase == TouchPhase::Ended
+                        && self.tap_possible
+                        && self.fingers.is_empty()
+                        && now - self.started <= TAP_SECONDS
+                        && event.position.distance(finger.start) <= TAP_SLOP;
+                }
+            }
+        }
+        gestures
+    }
+
+    fn moved(&mut self, event: &TouchInput, gestures: &mut TouchGestures) {
+        let index = match self.fingers.iter().position(|f| f.id == event.id) {
+            Some(index) => index,
+            None => return,
+        };
+        let previous = self.fingers[index].position;
+        if self.fingers[index].start.distance(event.position) > TAP_SLOP {
+            self.tap_possible = false;
+        }
+        self.fingers[index].position = event.position;
+
+        match self.fingers.len() {
+            1 if !self.multi_touch && !self.tap_possible => {
+                gestures.orbit += event.position - previous;
+            }
+            // the first two fingers pan and pinch, any more are ignored
+            n if n >= 2 && index < 2 => {
+                let other = self.fingers[1 - index].position;
+                // the point between the fingers moves half as far as the finger
+                gestures.pan += (event.position - previous) / 2.0;
+                let before = previous.distance(other);
+                let after = event.position.distance(other);
+                if before > 0.0 && after > 0.0 {
+                    gestures.pinch *= after / before;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn recognize_gestures(
+    time: Res<Time>,
+    mut events: EventReader<TouchInput>,
+    mut recognizer: ResMut<GestureRecognizer>,
+    mut gestures: ResMut<TouchGestures>,
+) {
+    *gestures = recognizer.update(events.iter(), time.seconds_since_startup());
+}
+
+pub fn reset_gestures(
+    mut recognizer: ResMut<GestureRecognizer>,
+    mut gestures: ResMut<TouchGestures>,
+) {
+    *recognizer = GestureRecognizer::default();
+    *gestures = TouchGestures::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(phase: TouchPhase, id: u64, x: f32, y: f32) -> TouchInput {
+        TouchInput {
+            phase,
+            position: Vec2::new(x, y),
+            force: None,
+            id,
+        }
+    }
+
+    // Feeds one frame of events per entry, 1/60 s apart, and returns the gestures of each
+    fn play(frames: &[Vec<TouchInput>]) -> Vec<TouchGestures> {
+        let mut recognizer = GestureRecognizer::default();
+        frames
+            .iter()
+            .enumerate()
+            .map(|(i, events)| recognizer.update(events, i as f64 / 60.0))
+            .collect()
+    }
+
+    #[test]
+    fn quick_touch_is_a_tap() {
+        let gestures = play(&[
+            vec![touch(TouchPhase::Started, 0, 100.0, 200.0)],
+            vec![touch(TouchPhase::Moved, 0, 103.0, 201.0)],
+            vec![touch(TouchPhase::Ended, 0, 103.0, 201.0)],
+        ]);
+        assert!(gestures[2].tap);
+        // a wobble within the slop does not orbit
+        assert_eq!(gestures[1].orbit, Vec2::ZERO);
+    }
+
+    #[test]
+    fn long_press_is_not_a_tap() {
+        let mut frames = vec![vec![touch(TouchPhase::Started, 0, 100.0, 200.0)]];
+        frames.extend((0..30).map(|_| Vec::new()));
+        frames.push(vec![touch(TouchPhase::Ended, 0, 100.0, 200.0)]);
+        assert!(play(&frames).iter().all(|g| !g.tap));
+    }
+
+    #[test]
+    fn one_finger_drag_orbits() {
+        let gestures = play(&[
+            vec![touch(TouchPhase::Started, 0, 100.0, 100.0)],
+            vec![touch(TouchPhase::Moved, 0, 120.0, 100.0)],
+            vec![touch(TouchPhase::Moved, 0, 150.0, 90.0)],
+            vec![touch(TouchPhase::Ended, 0, 150.0, 90.0)],
+        ]);
+        assert_eq!(gestures[1].orbit, Vec2::new(20.0, 0.0));
+        assert_eq!(gestures[2].orbit, Vec2::new(30.0, -10.0));
+        assert!(!gestures[3].tap);
+    }
+
+    #[test]
+    fn two_finger_drag_pans_without_zooming() {
+        let gestures = play(&[
+            vec![
+                touch(TouchPhase::Started, 0, 100.0, 100.0),
+                touch(TouchPhase::Started, 1, 200.0, 100.0),
+            ],
+            vec![
+                touch(TouchPhase::Moved, 0, 100.0, 140.0),
+                touch(TouchPhase::Moved, 1, 200.0, 140.0),
+            ],
+        ]);
+        assert_eq!(gestures[1].pan, Vec2::new(0.0, 40.0));
+        assert!((gestures[1].pinch - 1.0).abs() < 1e-5);
+        assert_eq!(gestures[1].orbit, Vec2::ZERO);
+    }
+
+    #[test]
+    fn spreading_fingers_pinches() {
+        let gestures = play(&[
+            vec![
+                touch(TouchPhase::Started, 0, 100.0, 100.0),
+                touch(TouchPhase::Started, 1, 200.0, 100.0),
+            ],
+            vec![
+                touch(TouchPhase::Moved, 0, 50.0, 100.0),
+                touch(TouchPhase::Moved, 1, 250.0, 100.0),
+            ],
+        ]);
+        assert!((gestures[1].pinch - 2.0).abs() < 1e-5);
+        assert_eq!(gestures[1].pan, Vec2::ZERO);
+    }
+
+    #[test]
+    fn lifting_one_finger_of_a_pinch_does_not_orbit_or_tap() {
+        let gestures = play(&[
+            vec![
+                touch(TouchPhase::Started, 0, 100.0, 100.0),
+                touch(TouchPhase::Started, 1, 200.0, 100.0),
+            ],
+            vec![touch(TouchPhase::Ended, 1, 200.0, 100.0)],
+            vec![touch(TouchPhase::Moved, 0, 160.0, 100.0)],
+            vec![touch(TouchPhase::Ended, 0, 160.0, 100.0)],
+        ]);
+        assert!(gestures.iter().all(|g| g.orbit == Vec2::ZERO && !g.tap));
+    }
+}
@@ -0,0 +1,48 @@
+// Camera views the player stores with Ctrl+1..4 and flies back to with 1..4
+
+use super::super::actions::{VIEWPOINT_KEYS, VIEWPOINT_STORE_KEYS};
+use super::super::settings::Settings;
+use super::camera::{CameraLimits, CameraTransition, PanOrbitCamera, SmoothedCamera};
+use super::WHALE_MODEL;
+use bevy::prelude::*;
+
+pub fn store_and_recall_viewpoints(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    limits: Res<CameraLimits>,
+    mut settings: ResMut<Settings>,
+    mut camera_query: Query<(Entity, &PanOrbitCamera, &mut SmoothedCamera)>,
+) {
+    let slot = match VIEWPOINT_KEYS
+        .iter()
+        .position(|key| keys.just_pressed(*key))
+    {
+        Some(slot) => slot,
+        None => return,
+    };
+    let store = keys.any_pressed(VIEWPOINT_STORE_KEYS);
+    for (entity, pan_orbit, mut smoothed) in camera_query.iter_mut() {
+        if store {
+            let slots = settings
+                .viewpoints
+                .entry(WHALE_MODEL.to_string())
+                .or_default();
+            slots[slot] = Some(pan_orbit.viewpoint());
+            info!("Stored camera view {}", slot + 1);
+            continue;
+        }
+        let viewpoint = settings
+            .viewpoints
+            .get(WHALE_MODEL)
+            .and_then(|slots| slots[slot]);
+        if let Some(viewpoint) = viewpoint {
+            let mut view = PanOrbitCamera::from_viewpoint(&viewpoint);
+            // the file may be hand edited
+            limits.clamp(&mut view);
+            smoothed.orbit_velocity = Vec2::ZERO;
+            commands
+                .entity(entity)
+                .insert(CameraTransition::new(*pan_orbit, view));
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use super::settings::Settings;
+use bevy::prelude::*;
+use fluent::concurrent::FluentBundle;
+use fluent::{FluentArgs, FluentResource};
+use serde::{Deserialize, Serialize};
+use unic_langid::LanguageIdentifier;
+
+/// Languages the UI is translated to, each one has a `assets/locale/<id>/main.ftl` file
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    fn id(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+
+    // The translations are compiled in, so they are available on the first frame and on the web
+    fn source(&self) -> &'static str {
+        match self {
+            Language::English => include_str!("../../assets/locale/en/main.ftl"),
+            Language::German => include_str!("../../assets/locale/de/main.ftl"),
+        }
+    }
+
+    /// Name of the language in that language, as shown by the language picker
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+}
+
+/// Looks up translated UI strings
+pub struct Localization {
+    bundles: Vec<(Language, FluentBundle<FluentResource>)>,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        let bundles = Language::ALL
+            .iter()
+            .map(|language| {
+                let id: LanguageIdentifier = language.id().parse().expect("invalid language id");
+                let resource = FluentResource::try_new(language.source().to_string())
+                    .unwrap_or_else(|(_, errors)| {
+                        panic!("invalid {} translations: {:?}", language.id(), errors)
+                    });
+                let mut bundle = FluentBundle::new_concurrent(vec![id]);
+                // no unicode isolation marks around arguments, the UI font has no glyphs for them
+                bundle.set_use_isolating(false);
+                bundle
+                    .add_resource(resource)
+                    .expect("duplicate translation key");
+                (*language, bundle)
+            })
+            .collect();
+        Localization { bundles }
+    }
+}
+
+impl Localization {
+    pub fn get(&self, language: Language, key: &str) -> String {
+        self.format(language, key, None)
+    }
+
+    /// Translates `key`, falling back to English and then to the key itself
+    pub fn format(&self, language: Language, key: &str, args: Option<&FluentArgs>) -> String {
+        [language, Language::English]
+            .iter()
+            .find_map(|language| {
+                let bundle = &self.bundle(*language)?;
+                let pattern = bundle.get_message(key)?.value()?;
+                let mut errors = vec![];
+                Some(
+                    bundle
+                        .format_pattern(pattern, args, &mut errors)
+                        .into_owned(),
+                )
+            })
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Formats a number with the decimal separator of `language`
+    pub fn decimal(&self, language: Language, value: f32, precision: usize) -> String {
+        format!("{:.*}", precision, value).replace('.', &self.get(language, "decimal-separator"))
+    }
+
+    #[cfg(test)]
+    pub fn has(&self, language: Language, key: &str) -> bool {
+        self.bundle(language)
+            .is_some_and(|bundle| bundle.has_message(key))
+    }
+
+    fn bundle(&self, language: Language) -> Option<&FluentBundle<FluentResource>> {
+        self.bundles
+            .iter()
+            .find(|(l, _)| *l == language)
+            .map(|(_, bundle)| bundle)
+    }
+}
+
+/// Keeps the first section of a `Text` translated to the current language
+#[derive(Component)]
+pub struct Localized(pub String);
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Localization>()
+            .add_system_to_stage(CoreStage::PostUpdate, update_localized_text);
+    }
+}
+
+fn update_localized_text(
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    mut language: Local<Option<Language>>,
+    mut query: Query<(ChangeTrackers<Localized>, &Localized, &mut Text)>,
+) {
+    // everything is retranslated when the language changes, otherwise only new texts
+    let changed = *language != Some(settings.language);
+    *language = Some(settings.language);
+    for (tracker, localized, mut text) in query.iter_mut() {
+        if changed || tracker.is_added() {
+            text.sections[0].value = localization.get(settings.language, &localized.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluent_syntax::ast::Entry;
+    use std::collections::BTreeSet;
+
+    fn keys(language: Language) -> BTreeSet<String> {
+        fluent_syntax::parser::parse(language.source())
+            .unwrap_or_else(|(_, errors)| panic!("{:?}: {:?}", language, errors))
+            .body
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Message(message) => Some(message.id.name.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_locale_has_every_key() {
+        let english = keys(Language::English);
+        for language in Language::ALL {
+            let translated = keys(language);
+            let missing: Vec<_> = english.difference(&translated).collect();
+            let unknown: Vec<_> = translated.difference(&english).collect();
+            assert!(
+                missing.is_empty(),
+                "{:?} is missing {:?}",
+                language,
+                missing
+            );
+            assert!(
+                unknown.is_empty(),
+                "{:?} has unknown {:?}",
+                language,
+                unknown
+            );
+        }
+    }
+
+    #[test]
+    fn formats_decimals_per_language() {
+        let localization = Localization::default();
+        assert_eq!(localization.decimal(Language::English, 1.5, 2), "1.50");
+        assert_eq!(localization.decimal(Language::German, 1.5, 2), "1,50");
+    }
+}
@@ -1,25 +1,138 @@
 // disable console opening on windows
 #![windows_subsystem = "windows"]
 
+mod actions;
+mod cli;
 mod game;
+mod locale;
 mod menu;
+mod settings;
 
+use bevy::app::{ScheduleRunnerPlugin, ScheduleRunnerSettings};
+use bevy::gilrs::GilrsPlugin;
 use bevy::prelude::*;
+use bevy::render::options::WgpuOptions;
+use bevy::winit::WinitPlugin;
+use bevy_mod_picking::PickingPluginsState;
 use bevy_obj::*;
+use std::process;
+use std::time::Duration;
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 enum GameState {
     Menu,
     Game,
+    /// Pushed over `Game`, which keeps its entities but stops updating
+    Paused,
+    /// Pushed over `Game` once the whale is overrun, which ends the run
+    Overrun,
 }
 
 fn main() {
-    App::new()
-        .insert_resource(Msaa { samples: 4 })
-        .add_plugins(DefaultPlugins)
-        .add_plugin(ObjPlugin)
-        .add_state(GameState::Game)
+    attach_parent_console();
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Run(options)) => options,
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(e) => exit_with_error(&format!("{}\nRun with --help to see the options", e)),
+    };
+
+    // read before building the app so the window and renderer start with the saved options
+    let settings = match &options.config {
+        Some(path) => settings::load_file(path).unwrap_or_else(|e| {
+            exit_with_error(&format!("could not read {}: {}", path.display(), e))
+        }),
+        None => settings::load(),
+    };
+    let playback = match &options.replay {
+        Some(path) => game::Playback::new(
+            game::load_replay(path).unwrap_or_else(|e| {
+                exit_with_error(&format!("could not read {}: {}", path.display(), e))
+            }),
+            options.headless,
+        ),
+        None => game::Playback::default(),
+    };
+
+    let mut window = WindowDescriptor {
+        mode: settings.window_mode(),
+        ..Default::default()
+    };
+    if let Some((width, height)) = options.window {
+        window.width = width;
+        window.height = height;
+    }
+
+    let mut app = App::new();
+    app.insert_resource(window)
+        .insert_resource(Msaa {
+            samples: settings.msaa_samples,
+        })
+        .insert_resource(settings)
+        .insert_resource(settings::SettingsPath(options.config))
+        .insert_resource(game::RunOptions {
+            seed: options.seed,
+            mode: options.mode,
+            exit_when_overrun: options.headless,
+        })
+        .insert_resource(playback);
+
+    if options.headless {
+        // no window, input devices or GPU: the app is driven by a fixed rate loop instead of
+        // winit, and there is no cursor to pick barnacles with
+        app.insert_resource(WgpuOptions {
+            backends: None,
+            ..Default::default()
+        })
+        .insert_resource(PickingPluginsState {
+            enable_picking: false,
+            enable_highlighting: false,
+            enable_interacting: false,
+            update_debug_cursor: false,
+            print_debug_events: false,
+        })
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )))
+        .add_plugins_with(DefaultPlugins, |group| {
+            group.disable::<WinitPlugin>().disable::<GilrsPlugin>()
+        })
+        .add_plugin(ScheduleRunnerPlugin);
+    } else {
+        app.add_plugins(DefaultPlugins);
+    }
+
+    app.add_plugin(ObjPlugin)
+        .add_plugin(settings::SettingsPlugin)
+        .add_plugin(actions::ActionsPlugin)
+        .add_plugin(locale::LocalePlugin)
+        .add_state(options.start)
         .add_plugin(menu::MenuPlugin)
         .add_plugin(game::GamePlugin)
         .run();
 }
+
+/// Without a console of its own the game can't print `--help` or CLI errors on windows,
+/// so it borrows the one of the terminal it was started from, if any
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // fails when started from the explorer, which has no console to attach to
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(2);
+}
@@ -0,0 +1,284 @@
+// Spawns the UI nodes of a `MenuScreen`
+
+use super::super::locale::{Localization, Localized};
+use super::super::settings::Settings;
+use super::credits::{Credits, CreditsScroll};
+use super::definition::{MenuItem, MenuScreen};
+use super::{
+    action_key, binding_label, OnMenuScreen, RebindButton, RebindMessage, SelectedOption,
+    NORMAL_BUTTON, PRESSED_BUTTON, TEXT_COLOR,
+};
+use bevy::prelude::*;
+
+/// What the items of a screen need to display themselves
+pub struct BuildContext<'a> {
+    pub font: Handle<Font>,
+    pub localization: &'a Localization,
+    pub settings: &'a Settings,
+    pub credits: &'a Credits,
+}
+
+impl BuildContext<'_> {
+    fn text(&self, key: &str) -> String {
+        self.localization.get(self.settings.language, key)
+    }
+
+    fn text_style(&self, font_size: f32) -> TextStyle {
+        TextStyle {
+            font: self.font.clone(),
+            font_size,
+            color: TEXT_COLOR,
+        }
+    }
+}
+
+pub fn spawn_screen(commands: &mut Commands, screen: &MenuScreen, context: &BuildContext) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: Rect::all(Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::CRIMSON.into(),
+            ..Default::default()
+        })
+        .insert(OnMenuScreen)
+        .with_children(|parent| {
+            for item in screen.items.iter() {
+                spawn_item(parent, item, context);
+            }
+        });
+}
+
+fn spawn_item(parent: &mut ChildBuilder, item: &MenuItem, context: &BuildContext) {
+    match item {
+        MenuItem::Title(key) => {
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(50.0)),
+                        ..Default::default()
+                    },
+                    text: Text::with_section(
+                        context.text(key),
+                        context.text_style(80.0),
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(Localized(key.clone()));
+        }
+        MenuItem::Label(key) => {
+            spawn_label(parent, key, None, context);
+        }
+        MenuItem::Button { label, action } => {
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(250.0), Val::Px(65.0)),
+                        margin: Rect::all(Val::Px(20.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: NORMAL_BUTTON.into(),
+                    ..Default::default()
+                })
+                .insert(action.clone())
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                context.text(label),
+                                context.text_style(40.0),
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        })
+                        .insert(Localized(label.clone()));
+                });
+        }
+        MenuItem::Row(items) => {
+            spawn_row(parent, |parent| {
+                for item in items {
+                    spawn_item(parent, item, context);
+                }
+            });
+        }
+        // a label followed by a button for each possible value
+        MenuItem::Setting { label, values } => {
+            spawn_row(parent, |parent| {
+                spawn_label(parent, label, Some(300.0), context);
+                for value in values {
+                    let selected = value.is_current(context.settings);
+                    let mut entity = parent.spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(110.0), Val::Px(36.0)),
+                            margin: Rect::all(Val::Px(4.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        color: if selected {
+                            PRESSED_BUTTON.into()
+                        } else {
+                            NORMAL_BUTTON.into()
+                        },
+                        ..Default::default()
+                    });
+                    entity.insert(*value).with_children(|parent| {
+                        let mut label = parent.spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                value.label(context.localization, context.settings.language),
+                                context.text_style(22.0),
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        });
+                        if let Some(key) = value.label_key() {
+                            label.insert(Localized(key.to_string()));
+                        }
+                    });
+                    if selected {
+                        entity.insert(SelectedOption);
+                    }
+                }
+            });
+        }
+        // one row per action: its name and a button showing the binding, click it to rebind
+        MenuItem::Bindings(actions) => {
+            for &action in actions {
+                spawn_row(parent, |parent| {
+                    spawn_label(parent, action_key(action), Some(200.0), context);
+                    parent
+                        .spawn_bundle(ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(220.0), Val::Px(36.0)),
+                                margin: Rect::all(Val::Px(4.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            color: NORMAL_BUTTON.into(),
+                            ..Default::default()
+                        })
+                        .insert(RebindButton(action))
+                        .with_children(|parent| {
+                            parent.spawn_bundle(TextBundle {
+                                text: Text::with_section(
+                                    binding_label(context.localization, context.settings, action),
+                                    context.text_style(22.0),
+                                    Default::default(),
+                                ),
+                                ..Default::default()
+                            });
+                        });
+                });
+            }
+        }
+        MenuItem::BindingMessage => {
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(4.0)),
+                        ..Default::default()
+                    },
+                    text: Text::with_section("", context.text_style(22.0), Default::default()),
+                    ..Default::default()
+                })
+                .insert(RebindMessage);
+        }
+        MenuItem::Credits => {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(700.0), Val::Px(400.0)),
+                        margin: Rect::all(Val::Px(20.0)),
+                        flex_direction: FlexDirection::ColumnReverse,
+                        overflow: Overflow::Hidden,
+                        ..Default::default()
+                    },
+                    color: Color::rgb(0.10, 0.10, 0.10).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::ColumnReverse,
+                                flex_shrink: 0.0,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .insert(CreditsScroll::default())
+                        .with_children(|parent| {
+                            for credit in context.credits.0.iter() {
+                                spawn_credit_text(parent, &credit.source, 30.0, context);
+                                spawn_credit_text(parent, &credit.text, 18.0, context);
+                            }
+                        });
+                });
+        }
+    }
+}
+
+fn spawn_credit_text(
+    parent: &mut ChildBuilder,
+    text: &str,
+    font_size: f32,
+    context: &BuildContext,
+) {
+    parent.spawn_bundle(TextBundle {
+        style: Style {
+            flex_shrink: 0.0,
+            max_size: Size::new(Val::Px(660.0), Val::Undefined),
+            margin: Rect::all(Val::Px(8.0)),
+            ..Default::default()
+        },
+        text: Text::with_section(
+            text,
+            context.text_style(font_size),
+            TextAlignment {
+                vertical: VerticalAlign::Top,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        ..Default::default()
+    });
+}
+
+fn spawn_row(parent: &mut ChildBuilder, children: impl FnOnce(&mut ChildBuilder)) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::CRIMSON.into(),
+            ..Default::default()
+        })
+        .with_children(children);
+}
+
+fn spawn_label(parent: &mut ChildBuilder, key: &str, width: Option<f32>, context: &BuildContext) {
+    parent
+        .spawn_bundle(TextBundle {
+            style: Style {
+                size: Size::new(width.map_or(Val::Auto, Val::Px), Val::Auto),
+                margin: Rect::all(Val::Px(4.0)),
+                ..Default::default()
+            },
+            text: Text::with_section(
+                context.text(key),
+                context.text_style(22.0),
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(Localized(key.to_string()));
+}
@@ -0,0 +1,139 @@
+// Credits shown on the credits screen, read from the attribution and license files that
+// come with the assets
+
+use bevy::asset::AssetServerSettings;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+// Automatic scrolling speed, in pixels per second
+const SCROLL_SPEED: f32 = 30.0;
+const LINE_SCROLL: f32 = 20.0;
+
+/// One credited asset file
+pub struct Credit {
+    /// Path of the file in the assets folder
+    pub source: String,
+    pub text: String,
+}
+
+/// Every credit found in the assets folder at startup
+pub struct Credits(pub Vec<Credit>);
+
+impl FromWorld for Credits {
+    fn from_world(world: &mut World) -> Self {
+        let folder = world
+            .get_resource::<AssetServerSettings>()
+            .map(|settings| settings.asset_folder.clone())
+            .unwrap_or_else(|| AssetServerSettings::default().asset_folder);
+        let mut credits = read_credits(&folder);
+        credits.sort_by(|a, b| a.source.cmp(&b.source));
+        Credits(credits)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_credit_file(name: &str) -> bool {
+    let lowercase = name.to_lowercase();
+    name.ends_with(".ATTRIBUTION")
+        || lowercase.starts_with("license")
+        || lowercase.starts_with("licence")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_credits(folder: &str) -> Vec<Credit> {
+    let root = bevy::asset::FileAssetIo::get_root_path().join(folder);
+    let mut credits = Vec::new();
+    let mut directories = vec![root.clone()];
+    while let Some(directory) = directories.pop() {
+        let entries = match std::fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Could not read {}: {}", directory.display(), e);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                directories.push(path);
+            } else if is_credit_file(&name) {
+                match std::fs::read_to_string(&path) {
+                    Ok(text) => credits.push(Credit {
+                        source: path
+                            .strip_prefix(&root)
+                            .unwrap_or(&path)
+                            .to_string_lossy()
+                            .replace('\\', "/"),
+                        text: clean_up(&text),
+                    }),
+                    Err(e) => warn!("Could not read {}: {}", path.display(), e),
+                }
+            }
+        }
+    }
+    credits
+}
+
+// The browser can't list the assets folder, so the web build knows its credit files in advance
+#[cfg(target_arch = "wasm32")]
+include!(concat!(env!("OUT_DIR"), "/credit_files.rs"));
+
+#[cfg(target_arch = "wasm32")]
+fn read_credits(_folder: &str) -> Vec<Credit> {
+    CREDIT_FILES
+        .iter()
+        .map(|(source, text)| Credit {
+            source: source.to_string(),
+            text: clean_up(text),
+        })
+        .collect()
+}
+
+// Strips the indentation, which the UI font has no glyph for, and runs of blank lines
+fn clean_up(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Column of credits moving up inside a clipping node, wrapping around at the end.
+/// The mouse wheel scrolls it by hand.
+#[derive(Component, Default)]
+pub struct CreditsScroll {
+    offset: f32,
+}
+
+pub fn scroll_credits(
+    time: Res<Time>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut scroll_query: Query<(&mut CreditsScroll, &mut Style, &Node, &Parent)>,
+    panel_query: Query<&Node>,
+) {
+    let wheel: f32 = wheel_events
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y * LINE_SCROLL,
+            MouseScrollUnit::Pixel => event.y,
+        })
+        .sum();
+    for (mut scroll, mut style, node, parent) in scroll_query.iter_mut() {
+        let panel_height = panel_query.get(parent.0).map_or(0.0, |panel| panel.size.y);
+        scroll.offset += SCROLL_SPEED * time.delta_seconds() - wheel;
+        if scroll.offset > node.size.y {
+            // start again from below the panel
+            scroll.offset = -panel_height;
+        } else if scroll.offset < -panel_height {
+            scroll.offset = -panel_height;
+        }
+        style.position.top = Val::Px(-scroll.offset);
+    }
+}
@@ -0,0 +1,69 @@
+// Menu screens described in `assets/menus/*.menu.ron`, turned into UI nodes by `builder`
+
+use super::super::actions::Action;
+use super::{MenuButtonAction, SettingValue};
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// The browser can't list the assets folder, so the web build knows its menu files in advance
+#[cfg(any(target_arch = "wasm32", test))]
+include!(concat!(env!("OUT_DIR"), "/menu_files.rs"));
+
+/// Every screen of a menu file, by name. Buttons open screens with `Open("<name>")`.
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "b9865497-8773-47f5-8eda-b394428e7789"]
+pub struct MenuDefinition {
+    pub screens: HashMap<String, MenuScreen>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MenuScreen {
+    pub items: Vec<MenuItem>,
+}
+
+/// Building blocks of a screen, stacked from top to bottom. Texts are translation keys.
+#[derive(Debug, Deserialize)]
+pub enum MenuItem {
+    Title(String),
+    Label(String),
+    Button {
+        label: String,
+        action: MenuButtonAction,
+    },
+    /// Items side by side
+    Row(Vec<MenuItem>),
+    /// A label followed by one button per value, the current value is highlighted
+    Setting {
+        label: String,
+        values: Vec<SettingValue>,
+    },
+    /// These rebindable actions with their current binding
+    Bindings(Vec<Action>),
+    /// Explains why the last rebinding was refused
+    BindingMessage,
+    /// The attribution and license texts of the assets, scrolling
+    Credits,
+}
+
+#[derive(Default)]
+pub struct MenuLoader;
+
+impl AssetLoader for MenuLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let definition: MenuDefinition = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(definition));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["menu.ron"]
+    }
+}
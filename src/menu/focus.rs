@@ -0,0 +1,182 @@
+// Moves a focus between menu buttons so the menus can be used without a mouse
+
+use super::super::actions::ActiveGamepad;
+use super::{button_color, AwaitingBinding, MenuButtonAction, SelectedOption};
+use bevy::prelude::*;
+
+// Tag component for the button that activation input applies to
+#[derive(Component)]
+pub struct Focused;
+
+// Requests from the devices able to drive the menus
+pub enum MenuNavigation {
+    /// Move the focus towards the given direction, in UI coordinates (y up)
+    Move(Vec2),
+    Activate,
+    Back,
+}
+
+pub fn keyboard_menu_navigation(
+    keys: Res<Input<KeyCode>>,
+    awaiting: Res<AwaitingBinding>,
+    mut navigation: EventWriter<MenuNavigation>,
+) {
+    // the next key press is the new binding of an action
+    if awaiting.0.is_some() {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Up) {
+        navigation.send(MenuNavigation::Move(Vec2::Y));
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        navigation.send(MenuNavigation::Move(-Vec2::Y));
+    }
+    if keys.just_pressed(KeyCode::Left) {
+        navigation.send(MenuNavigation::Move(-Vec2::X));
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        navigation.send(MenuNavigation::Move(Vec2::X));
+    }
+    if keys.any_just_pressed([KeyCode::Return, KeyCode::NumpadEnter]) {
+        navigation.send(MenuNavigation::Activate);
+    }
+    if keys.any_just_pressed([KeyCode::Back, KeyCode::Escape]) {
+        navigation.send(MenuNavigation::Back);
+    }
+}
+
+pub fn gamepad_menu_navigation(
+    gamepad: Res<ActiveGamepad>,
+    buttons: Res<Input<GamepadButton>>,
+    mut navigation: EventWriter<MenuNavigation>,
+) {
+    let gamepad = match gamepad.0 {
+        Some(gamepad) => gamepad,
+        None => return,
+    };
+    let pressed = |button| buttons.just_pressed(GamepadButton(gamepad, button));
+
+    if pressed(GamepadButtonType::DPadUp) {
+        navigation.send(MenuNavigation::Move(Vec2::Y));
+    }
+    if pressed(GamepadButtonType::DPadDown) {
+        navigation.send(MenuNavigation::Move(-Vec2::Y));
+    }
+    if pressed(GamepadButtonType::DPadLeft) {
+        navigation.send(MenuNavigation::Move(-Vec2::X));
+    }
+    if pressed(GamepadButtonType::DPadRight) {
+        navigation.send(MenuNavigation::Move(Vec2::X));
+    }
+    if pressed(GamepadButtonType::South) {
+        navigation.send(MenuNavigation::Activate);
+    }
+    if pressed(GamepadButtonType::East) {
+        navigation.send(MenuNavigation::Back);
+    }
+}
+
+type NavigableButtons<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static mut Interaction,
+        &'static mut UiColor,
+        Option<&'static SelectedOption>,
+        Option<&'static Focused>,
+        Option<&'static MenuButtonAction>,
+    ),
+    With<Button>,
+>;
+
+// Activating a button sets its `Interaction` to `Clicked` for one frame, so every system
+// reacting to mouse clicks reacts to it as well.
+pub fn navigate_menu(
+    mut commands: Commands,
+    mut navigation: EventReader<MenuNavigation>,
+    mut buttons: NavigableButtons,
+    mut activated: Local<Option<Entity>>,
+) {
+    if let Some(entity) = activated.take() {
+        if let Ok((_, _, mut interaction, ..)) = buttons.get_mut(entity) {
+            if *interaction == Interaction::Clicked {
+                *interaction = Interaction::None;
+            }
+        }
+    }
+
+    for event in navigation.iter() {
+        let focused = buttons
+            .iter()
+            .find(|(.., focused, _)| focused.is_some())
+            .map(|(entity, transform, ..)| (entity, transform.translation.truncate()));
+
+        let target = match (event, focused) {
+            (MenuNavigation::Move(direction), Some((current, position))) => {
+                let next = buttons
+                    .iter()
+                    .filter(|(entity, ..)| *entity != current)
+                    .filter_map(|(entity, transform, ..)| {
+                        let offset = transform.translation.truncate() - position;
+                        let along = offset.dot(*direction);
+                        let across = offset.perp_dot(*direction).abs();
+                        // prefer buttons straight ahead over closer ones off to the side
+                        (along > 0.0).then_some((entity, along + 2.0 * across))
+                    })
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(entity, _)| entity);
+                match next {
+                    Some(next) => next,
+                    None => continue,
+                }
+            }
+            // nothing focused yet: start at the top left button
+            (MenuNavigation::Move(_), None) => {
+                match buttons
+                    .iter()
+                    .map(|(entity, transform, ..)| (entity, transform.translation))
+                    .min_by(|(_, a), (_, b)| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)))
+                {
+                    Some((entity, _)) => entity,
+                    None => continue,
+                }
+            }
+            (MenuNavigation::Activate, Some((current, _))) => {
+                activate(&mut buttons, current, &mut activated);
+                continue;
+            }
+            (MenuNavigation::Back, _) => {
+                let back = buttons
+                    .iter()
+                    .find(|(.., action)| matches!(action, Some(MenuButtonAction::Back)))
+                    .map(|(entity, ..)| entity);
+                if let Some(back) = back {
+                    activate(&mut buttons, back, &mut activated);
+                }
+                continue;
+            }
+            (MenuNavigation::Activate, None) => continue,
+        };
+
+        if let Some((current, _)) = focused {
+            commands.entity(current).remove::<Focused>();
+            if let Ok((_, _, interaction, mut color, selected, ..)) = buttons.get_mut(current) {
+                *color = button_color(*interaction, selected.is_some(), false);
+            }
+        }
+        commands.entity(target).insert(Focused);
+        if let Ok((_, _, interaction, mut color, selected, ..)) = buttons.get_mut(target) {
+            *color = button_color(*interaction, selected.is_some(), true);
+        }
+    }
+}
+
+fn activate(buttons: &mut NavigableButtons, entity: Entity, activated: &mut Option<Entity>) {
+    if let Ok((_, _, mut interaction, ..)) = buttons.get_mut(entity) {
+        *interaction = Interaction::Clicked;
+        *activated = Some(entity);
+    }
+}
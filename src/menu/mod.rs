@@ -1,8 +1,27 @@
 //adapted from menu example https://github.com/bevyengine/bevy/blob/main/examples/game/game_menu.rs
 
+mod builder;
+mod credits;
+mod definition;
+mod focus;
+
+use super::actions::{Action, Binding};
+use super::locale::{Language, Localization};
+use super::settings::{Difficulty, Settings};
 use super::GameState;
 use bevy::app::AppExit;
+use bevy::ecs::schedule::ShouldRun;
+use bevy::input::mouse::MouseWheel;
+use bevy::input::InputSystem;
 use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use builder::BuildContext;
+use credits::Credits;
+use definition::{MenuDefinition, MenuLoader};
+use fluent::FluentArgs;
+use focus::{Focused, MenuNavigation};
+use serde::Deserialize;
+use std::mem::discriminant;
 
 const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
 
@@ -10,48 +29,142 @@ pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_state(MenuState::Disabled)
-            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(menu_setup))
-            .add_system_set(SystemSet::on_enter(MenuState::Main).with_system(main_menu_setup))
-            .add_system_set(
-                SystemSet::on_exit(MenuState::Main).with_system(despawn_screen::<OnMainMenuScreen>),
+        app.add_asset::<MenuDefinition>()
+            .init_asset_loader::<MenuLoader>()
+            .init_resource::<Menus>()
+            .init_resource::<MenuStack>()
+            .init_resource::<Credits>()
+            .insert_resource(AwaitingBinding(None))
+            .add_event::<MenuNavigation>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                focus::keyboard_menu_navigation
+                    .with_run_criteria(menu_shown)
+                    .label("menu_navigation_input")
+                    .after(InputSystem),
             )
-            .add_system_set(
-                SystemSet::on_enter(MenuState::Settings).with_system(settings_menu_setup),
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                focus::gamepad_menu_navigation
+                    .with_run_criteria(menu_shown)
+                    .label("menu_navigation_input")
+                    .after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                focus::navigate_menu
+                    .after("menu_navigation_input")
+                    .after(UiSystem::Focus),
             )
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(menu_setup))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(pause_menu_setup))
             .add_system_set(
-                SystemSet::on_exit(MenuState::Settings)
-                    .with_system(despawn_screen::<OnSettingsMenuScreen>),
+                SystemSet::on_update(GameState::Paused)
+                    .with_system(resume_with_pause_action.before("menu_action")),
             )
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(close_menus))
+            .add_system_set(SystemSet::on_enter(GameState::Overrun).with_system(overrun_menu_setup))
+            .add_system_set(SystemSet::on_exit(GameState::Overrun).with_system(close_menus))
             .add_system_set(
-                SystemSet::on_update(GameState::Menu)
-                    .with_system(menu_action)
-                    .with_system(button_system),
+                SystemSet::new()
+                    .with_run_criteria(menu_shown)
+                    .with_system(menu_action.label("menu_action"))
+                    .with_system(setting_button)
+                    .with_system(capture_binding.before("rebind_button"))
+                    .with_system(rebind_button.label("rebind_button"))
+                    .with_system(update_binding_labels.after("rebind_button"))
+                    .with_system(button_system)
+                    .with_system(credits::scroll_credits),
             )
+            // also despawns the screen once the stack is cleared on the way into the game
+            .add_system(build_menu_screen.after("menu_action"))
             .add_system_set(
-                SystemSet::on_exit(GameState::Menu).with_system(despawn_screen::<MainMenuState>),
+                SystemSet::on_exit(GameState::Menu)
+                    .with_system(despawn_screen::<MainMenuState>)
+                    .with_system(close_menus),
             );
     }
 }
-// State used for the current menu screen
-#[derive(Clone, Eq, PartialEq, Debug, Hash)]
-enum MenuState {
-    Main,
-    Settings,
-    Disabled,
+
+// Run criteria of the menu systems: a menu is shown in every state but `Game`
+fn menu_shown(state: Res<State<GameState>>) -> ShouldRun {
+    match state.current() {
+        GameState::Menu | GameState::Paused | GameState::Overrun => ShouldRun::Yes,
+        GameState::Game => ShouldRun::No,
+    }
 }
 
-// Tag component used to tag entities added on the main menu screen
-#[derive(Component)]
-struct OnMainMenuScreen;
+/// The loaded menu definitions, one per file in `assets/menus`
+struct Menus {
+    definitions: Vec<Handle<MenuDefinition>>,
+}
+
+impl FromWorld for Menus {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        Menus {
+            definitions: load_menus(asset_server),
+        }
+    }
+}
+
+// Native builds list the folder, so a new menu file needs no rebuild. Sorted by path, since
+// the first file to have a screen wins.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_menus(asset_server: &AssetServer) -> Vec<Handle<MenuDefinition>> {
+    let handles = match asset_server.load_folder("menus") {
+        Ok(handles) => handles,
+        Err(e) => {
+            warn!("Could not list the menu files: {}", e);
+            return Vec::new();
+        }
+    };
+    let mut menus: Vec<(String, Handle<MenuDefinition>)> = handles
+        .into_iter()
+        .filter_map(|handle| {
+            let path = asset_server
+                .get_handle_path(&handle)?
+                .path()
+                .to_string_lossy()
+                .replace('\\', "/");
+            path.ends_with(".menu.ron").then(|| (path, handle.typed()))
+        })
+        .collect();
+    menus.sort_by(|(a, _), (b, _)| a.cmp(b));
+    menus.into_iter().map(|(_, handle)| handle).collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_menus(asset_server: &AssetServer) -> Vec<Handle<MenuDefinition>> {
+    definition::MENU_FILES
+        .iter()
+        .map(|file| asset_server.load(*file))
+        .collect()
+}
+
+/// Names of the open menu screens, the last one is shown and `Back` returns to the one before.
+/// Nothing is shown while it is empty.
+#[derive(Default)]
+pub struct MenuStack(Vec<String>);
 
 // Tag component used to tag entities added on the main menu screen
 #[derive(Component)]
 struct MainMenuState;
 
-// Tag component used to tag entities added on the settings menu screen
+// Tag component used to tag the nodes of the shown menu screen
 #[derive(Component)]
-struct OnSettingsMenuScreen;
+struct OnMenuScreen;
+
+// Button that rebinds an action, its text shows the current binding
+#[derive(Component)]
+struct RebindButton(Action);
+
+// Tag component for the text explaining why a binding was refused
+#[derive(Component)]
+struct RebindMessage;
+
+// Action waiting for the player to press its new binding
+struct AwaitingBinding(Option<Action>);
 
 const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
@@ -62,184 +175,419 @@ const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 #[derive(Component)]
 struct SelectedOption;
 
+// One possible value of a setting, attached to the button that selects it
+#[derive(Component, Clone, Copy, PartialEq, Debug, Deserialize)]
+enum SettingValue {
+    MasterVolume(u32),
+    SfxVolume(u32),
+    MusicVolume(u32),
+    MouseSensitivity(f32),
+    KeyboardCameraSpeed(f32),
+    InvertY(bool),
+    CameraSmoothing(f32),
+    OrbitInertia(bool),
+    RearView(bool),
+    RearViewSize(u32),
+    Msaa(u32),
+    Shadows(bool),
+    Fullscreen(bool),
+    Difficulty(Difficulty),
+    Language(Language),
+}
+
+impl SettingValue {
+    fn is_current(&self, settings: &Settings) -> bool {
+        match *self {
+            SettingValue::MasterVolume(v) => settings.master_volume == v,
+            SettingValue::SfxVolume(v) => settings.sfx_volume == v,
+            SettingValue::MusicVolume(v) => settings.music_volume == v,
+            SettingValue::MouseSensitivity(v) => settings.mouse_sensitivity == v,
+            SettingValue::KeyboardCameraSpeed(v) => settings.keyboard_camera_speed == v,
+            SettingValue::InvertY(v) => settings.invert_y == v,
+            SettingValue::CameraSmoothing(v) => settings.camera_smoothing == v,
+            SettingValue::OrbitInertia(v) => settings.orbit_inertia == v,
+            SettingValue::RearView(v) => settings.rear_view == v,
+            SettingValue::RearViewSize(v) => settings.rear_view_size == v,
+            SettingValue::Msaa(v) => settings.msaa_samples == v,
+            SettingValue::Shadows(v) => settings.shadows == v,
+            SettingValue::Fullscreen(v) => settings.fullscreen == v,
+            SettingValue::Difficulty(v) => settings.difficulty == v,
+            SettingValue::Language(v) => settings.language == v,
+        }
+    }
+
+    fn apply(&self, settings: &mut Settings) {
+        match *self {
+            SettingValue::MasterVolume(v) => settings.master_volume = v,
+            SettingValue::SfxVolume(v) => settings.sfx_volume = v,
+            SettingValue::MusicVolume(v) => settings.music_volume = v,
+            SettingValue::MouseSensitivity(v) => settings.mouse_sensitivity = v,
+            SettingValue::KeyboardCameraSpeed(v) => settings.keyboard_camera_speed = v,
+            SettingValue::InvertY(v) => settings.invert_y = v,
+            SettingValue::CameraSmoothing(v) => settings.camera_smoothing = v,
+            SettingValue::OrbitInertia(v) => settings.orbit_inertia = v,
+            SettingValue::RearView(v) => settings.rear_view = v,
+            SettingValue::RearViewSize(v) => settings.rear_view_size = v,
+            SettingValue::Msaa(v) => settings.msaa_samples = v,
+            SettingValue::Shadows(v) => settings.shadows = v,
+            SettingValue::Fullscreen(v) => settings.fullscreen = v,
+            SettingValue::Difficulty(v) => settings.difficulty = v,
+            SettingValue::Language(v) => settings.language = v,
+        }
+    }
+
+    // Translation key of the label, for values that are words
+    fn label_key(&self) -> Option<&'static str> {
+        match *self {
+            SettingValue::InvertY(true)
+            | SettingValue::OrbitInertia(true)
+            | SettingValue::RearView(true)
+            | SettingValue::Shadows(true) => Some("setting-on"),
+            SettingValue::InvertY(false)
+            | SettingValue::OrbitInertia(false)
+            | SettingValue::RearView(false)
+            | SettingValue::Shadows(false) => Some("setting-off"),
+            SettingValue::Msaa(1) => Some("setting-off"),
+            SettingValue::CameraSmoothing(v) if v <= 0.0 => Some("setting-off"),
+            SettingValue::Fullscreen(true) => Some("setting-fullscreen"),
+            SettingValue::Fullscreen(false) => Some("setting-windowed"),
+            SettingValue::Difficulty(Difficulty::Easy) => Some("difficulty-easy"),
+            SettingValue::Difficulty(Difficulty::Normal) => Some("difficulty-normal"),
+            SettingValue::Difficulty(Difficulty::Hard) => Some("difficulty-hard"),
+            _ => None,
+        }
+    }
+
+    fn label(&self, localization: &Localization, language: Language) -> String {
+        if let Some(key) = self.label_key() {
+            return localization.get(language, key);
+        }
+        match *self {
+            SettingValue::MasterVolume(v)
+            | SettingValue::SfxVolume(v)
+            | SettingValue::MusicVolume(v) => format!("{}", v),
+            SettingValue::MouseSensitivity(v) | SettingValue::KeyboardCameraSpeed(v) => {
+                format!("{}x", localization.decimal(language, v, 1))
+            }
+            SettingValue::CameraSmoothing(v) => {
+                format!("{}s", localization.decimal(language, v, 2))
+            }
+            SettingValue::Msaa(v) => format!("{}x", v),
+            SettingValue::RearViewSize(v) => format!("{}px", v),
+            SettingValue::Language(v) => v.native_name().to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
 // All actions that can be triggered from a button click
-#[derive(Component)]
+#[derive(Component, Clone, Debug, Deserialize)]
 enum MenuButtonAction {
     Play,
-    Settings,
-    BackToMainMenu,
+    /// Shows the screen with this name
+    Open(String),
+    /// Returns to the previous screen
+    Back,
+    ResetControls,
     Quit,
+    /// Closes the pause menu
+    Resume,
+    /// Starts a new run from the pause menu
+    Restart,
+    /// Ends the run from the pause menu
+    QuitToMainMenu,
+}
+
+// Color of a button, a focused button looks hovered
+fn button_color(interaction: Interaction, selected: bool, focused: bool) -> UiColor {
+    match (interaction, selected) {
+        (Interaction::Clicked, _) => PRESSED_BUTTON.into(),
+        (Interaction::None, true) if !focused => PRESSED_BUTTON.into(),
+        (Interaction::None, false) if !focused => NORMAL_BUTTON.into(),
+        (_, true) => HOVERED_PRESSED_BUTTON.into(),
+        (_, false) => HOVERED_BUTTON.into(),
+    }
 }
 
 // This system handles changing all buttons color based on mouse interaction
 #[allow(clippy::type_complexity)]
 fn button_system(
     mut interaction_query: Query<
-        (&Interaction, &mut UiColor, Option<&SelectedOption>),
+        (
+            &Interaction,
+            &mut UiColor,
+            Option<&SelectedOption>,
+            Option<&Focused>,
+        ),
         (Changed<Interaction>, With<Button>),
     >,
 ) {
-    for (interaction, mut color, selected) in interaction_query.iter_mut() {
-        *color = match (*interaction, selected) {
-            (Interaction::Clicked, _) => PRESSED_BUTTON.into(),
-            (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON.into(),
-            (Interaction::Hovered, None) => HOVERED_BUTTON.into(),
-            (Interaction::None, Some(_)) => PRESSED_BUTTON.into(),
-            (Interaction::None, None) => NORMAL_BUTTON.into(),
-        }
+    for (interaction, mut color, selected, focused) in interaction_query.iter_mut() {
+        *color = button_color(*interaction, selected.is_some(), focused.is_some());
     }
 }
 
-fn menu_setup(mut commands: Commands, mut menu_state: ResMut<State<MenuState>>) {
+fn menu_setup(mut commands: Commands, mut stack: ResMut<MenuStack>) {
     commands
         .spawn_bundle(UiCameraBundle::default())
         .insert(MainMenuState);
-    let _ = menu_state.set(MenuState::Main);
+    stack.0 = vec!["main".to_string()];
 }
 
-fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("fonts/Kenney Future.ttf");
-    // Common style for all buttons on the screen
-    let button_style = Style {
-        size: Size::new(Val::Px(250.0), Val::Px(65.0)),
-        margin: Rect::all(Val::Px(20.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..Default::default()
-    };
-    let button_text_style = TextStyle {
-        font: font.clone(),
-        font_size: 40.0,
-        color: TEXT_COLOR,
-    };
+// The pause menu is drawn by the camera of the game HUD, which stays while paused
+fn pause_menu_setup(mut stack: ResMut<MenuStack>) {
+    stack.0 = vec!["pause".to_string()];
+}
 
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                margin: Rect::all(Val::Auto),
-                flex_direction: FlexDirection::ColumnReverse,
-                align_items: AlignItems::Center,
-                ..Default::default()
+// Like the pause menu, the game stays in view behind it
+fn overrun_menu_setup(mut stack: ResMut<MenuStack>) {
+    stack.0 = vec!["overrun".to_string()];
+}
+
+// The pause action closes the pause menu, unless a screen opened from it is shown: there the
+// same key goes back to the pause menu
+fn resume_with_pause_action(
+    actions: Res<Input<Action>>,
+    stack: Res<MenuStack>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if actions.just_pressed(Action::Pause) && stack.0.len() <= 1 {
+        let _ = game_state.pop();
+    }
+}
+
+fn close_menus(mut stack: ResMut<MenuStack>) {
+    stack.0.clear();
+}
+
+// Replaces the shown screen by the top of the `MenuStack` when it changes, and rebuilds it
+// when a menu file is edited. Waits for the menu files to load if needed.
+#[allow(clippy::too_many_arguments)]
+fn build_menu_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    localization: Res<Localization>,
+    settings: Res<Settings>,
+    credits: Res<Credits>,
+    menus: Res<Menus>,
+    definitions: Res<Assets<MenuDefinition>>,
+    stack: Res<MenuStack>,
+    mut asset_events: EventReader<AssetEvent<MenuDefinition>>,
+    mut awaiting: ResMut<AwaitingBinding>,
+    mut pending: Local<bool>,
+    screen_query: Query<Entity, With<OnMenuScreen>>,
+) {
+    let reloaded = asset_events.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+            menus.definitions.contains(handle)
+        }
+        AssetEvent::Removed { .. } => false,
+    });
+    if !stack.is_changed() && !reloaded && !*pending {
+        return;
+    }
+
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    awaiting.0 = None;
+    *pending = false;
+
+    let name = match stack.0.last() {
+        Some(name) => name,
+        None => return,
+    };
+    let mut loaded = Vec::new();
+    for handle in menus.definitions.iter() {
+        match definitions.get(handle) {
+            Some(definition) => loaded.push(definition),
+            None => {
+                *pending = true;
+                return;
+            }
+        }
+    }
+    // screens are looked up by name across all menu files, the first file in order wins
+    match loaded
+        .iter()
+        .find_map(|definition| definition.screens.get(name))
+    {
+        Some(screen) => builder::spawn_screen(
+            &mut commands,
+            screen,
+            &BuildContext {
+                font: asset_server.load("fonts/Kenney Future.ttf"),
+                localization: &localization,
+                settings: &settings,
+                credits: &credits,
             },
-            color: Color::CRIMSON.into(),
-            ..Default::default()
-        })
-        .insert(OnMainMenuScreen)
-        .with_children(|parent| {
-            // Display the game name
-            parent.spawn_bundle(TextBundle {
-                style: Style {
-                    margin: Rect::all(Val::Px(50.0)),
-                    ..Default::default()
-                },
-                text: Text::with_section(
-                    "Laughing Barnacle",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 80.0,
-                        color: TEXT_COLOR,
-                    },
-                    Default::default(),
-                ),
-                ..Default::default()
-            });
-
-            // Display three buttons for each action available from the main menu:
-            // - new game
-            // - settings
-            // - quit
-            parent
-                .spawn_bundle(ButtonBundle {
-                    style: button_style.clone(),
-                    color: NORMAL_BUTTON.into(),
-                    ..Default::default()
-                })
-                .insert(MenuButtonAction::Play)
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle {
-                        text: Text::with_section(
-                            "New Game",
-                            button_text_style.clone(),
-                            Default::default(),
-                        ),
-                        ..Default::default()
-                    });
-                });
-            parent
-                .spawn_bundle(ButtonBundle {
-                    style: button_style.clone(),
-                    color: NORMAL_BUTTON.into(),
-                    ..Default::default()
-                })
-                .insert(MenuButtonAction::Settings)
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle {
-                        text: Text::with_section(
-                            "Settings",
-                            button_text_style.clone(),
-                            Default::default(),
-                        ),
-                        ..Default::default()
-                    });
-                });
-            parent
-                .spawn_bundle(ButtonBundle {
-                    style: button_style,
-                    color: NORMAL_BUTTON.into(),
-                    ..Default::default()
-                })
-                .insert(MenuButtonAction::Quit)
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle {
-                        text: Text::with_section("Quit", button_text_style, Default::default()),
-                        ..Default::default()
-                    });
-                });
-        });
+        ),
+        None => warn!("No menu screen named {:?}", name),
+    }
+}
+
+fn action_key(action: Action) -> &'static str {
+    match action {
+        Action::Orbit => "action-orbit",
+        Action::Pan => "action-pan",
+        Action::Zoom => "action-zoom",
+        Action::Remove => "action-remove",
+        Action::FocusBarnacle => "action-focus-barnacle",
+        Action::ResetView => "action-reset-view",
+        Action::Pause => "action-pause",
+        Action::Quit => "action-quit",
+        Action::CameraLeft => "action-camera-left",
+        Action::CameraRight => "action-camera-right",
+        Action::CameraUp => "action-camera-up",
+        Action::CameraDown => "action-camera-down",
+        Action::ZoomIn => "action-zoom-in",
+        Action::ZoomOut => "action-zoom-out",
+        Action::RearView => "action-rear-view",
+        Action::Viewpoint => "action-viewpoint",
+    }
+}
+
+fn binding_text(localization: &Localization, language: Language, binding: Binding) -> String {
+    match binding {
+        Binding::Key(key) => format!("{:?}", key),
+        Binding::Mouse(button) => {
+            let button = match button {
+                MouseButton::Other(button) => button.to_string(),
+                button => format!("{:?}", button),
+            };
+            let mut args = FluentArgs::new();
+            args.set("button", button);
+            localization.format(language, "binding-mouse", Some(&args))
+        }
+        Binding::MouseWheel => localization.get(language, "binding-wheel"),
+    }
 }
 
-fn settings_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let button_style = Style {
-        size: Size::new(Val::Px(200.0), Val::Px(65.0)),
-        margin: Rect::all(Val::Px(20.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..Default::default()
+fn binding_label(localization: &Localization, settings: &Settings, action: Action) -> String {
+    match settings.bindings.binding(action) {
+        Some(binding) => binding_text(localization, settings.language, binding),
+        None => "-".to_string(),
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn rebind_button(
+    interaction_query: Query<(&Interaction, &RebindButton), (Changed<Interaction>, With<Button>)>,
+    mut awaiting: ResMut<AwaitingBinding>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Clicked {
+            awaiting.0 = Some(button.0);
+        }
+    }
+}
+
+// Assigns the next key, mouse button or scroll to the action waiting for a binding.
+// Escape or a click on a menu button cancels, the button then does what it always does.
+// Runs before `rebind_button` so the click starting a rebind is not captured.
+#[allow(clippy::too_many_arguments)]
+fn capture_binding(
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    button_query: Query<&Interaction, With<Button>>,
+    mut ev_scroll: EventReader<MouseWheel>,
+    mut awaiting: ResMut<AwaitingBinding>,
+    mut settings: ResMut<Settings>,
+    localization: Res<Localization>,
+    mut message_query: Query<&mut Text, With<RebindMessage>>,
+) {
+    let scrolled = ev_scroll.iter().count() > 0;
+    let action = match awaiting.0 {
+        Some(action) => action,
+        None => return,
     };
-    let button_text_style = TextStyle {
-        font: asset_server.load("fonts/Kenney Future.ttf"),
-        font_size: 40.0,
-        color: TEXT_COLOR,
+    let over_button = || button_query.iter().any(|i| *i != Interaction::None);
+    if keys.just_pressed(KeyCode::Escape)
+        || (mouse.get_just_pressed().next().is_some() && over_button())
+    {
+        awaiting.0 = None;
+        return;
+    }
+
+    let binding = if let Some(key) = keys.get_just_pressed().next() {
+        Binding::Key(*key)
+    } else if let Some(button) = mouse.get_just_pressed().next() {
+        Binding::Mouse(*button)
+    } else if scrolled && action == Action::Zoom {
+        Binding::MouseWheel
+    } else {
+        return;
     };
 
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                margin: Rect::all(Val::Auto),
-                flex_direction: FlexDirection::ColumnReverse,
-                align_items: AlignItems::Center,
-                ..Default::default()
-            },
-            color: Color::CRIMSON.into(),
-            ..Default::default()
-        })
-        .insert(OnSettingsMenuScreen)
-        .with_children(|parent| {
-            // Display the back button to return to the main menu screen
-            parent
-                .spawn_bundle(ButtonBundle {
-                    style: button_style,
-                    color: NORMAL_BUTTON.into(),
-                    ..Default::default()
-                })
-                .insert(MenuButtonAction::BackToMainMenu)
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle {
-                        text: Text::with_section("Back", button_text_style, Default::default()),
-                        ..Default::default()
-                    });
-                });
-        });
+    awaiting.0 = None;
+    let message = match settings.bindings.rebind(action, binding) {
+        Ok(()) => String::new(),
+        Err(other) => {
+            let mut args = FluentArgs::new();
+            args.set(
+                "binding",
+                binding_text(&localization, settings.language, binding),
+            );
+            args.set(
+                "action",
+                localization.get(settings.language, action_key(other)),
+            );
+            localization.format(settings.language, "controls-conflict", Some(&args))
+        }
+    };
+    for mut text in message_query.iter_mut() {
+        text.sections[0].value = message.clone();
+    }
+}
+
+fn update_binding_labels(
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    awaiting: Res<AwaitingBinding>,
+    button_query: Query<(&RebindButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !settings.is_changed() && !awaiting.is_changed() {
+        return;
+    }
+    for (button, children) in button_query.iter() {
+        let label = if awaiting.0 == Some(button.0) {
+            localization.get(settings.language, "controls-press")
+        } else {
+            binding_label(&localization, &settings, button.0)
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+// This system updates the settings when a different value is chosen, and moves the
+// `SelectedOption` marker from the previous value of that setting to the clicked one
+#[allow(clippy::type_complexity)]
+fn setting_button(
+    interaction_query: Query<
+        (&Interaction, &SettingValue, Entity),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut selected_query: Query<(Entity, &SettingValue, &mut UiColor), With<SelectedOption>>,
+    mut commands: Commands,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, value, entity) in interaction_query.iter() {
+        if *interaction == Interaction::Clicked && !value.is_current(&settings) {
+            for (previous_button, previous_value, mut previous_color) in selected_query.iter_mut() {
+                if discriminant(previous_value) == discriminant(value) {
+                    *previous_color = NORMAL_BUTTON.into();
+                    commands.entity(previous_button).remove::<SelectedOption>();
+                }
+            }
+            commands.entity(entity).insert(SelectedOption);
+            value.apply(&mut settings);
+        }
+    }
 }
 
 #[allow(clippy::type_complexity)]
@@ -249,19 +597,35 @@ fn menu_action(
         (Changed<Interaction>, With<Button>),
     >,
     mut app_exit_events: EventWriter<AppExit>,
-    mut menu_state: ResMut<State<MenuState>>,
+    mut stack: ResMut<MenuStack>,
     mut game_state: ResMut<State<GameState>>,
+    mut settings: ResMut<Settings>,
 ) {
     for (interaction, menu_button_action) in interaction_query.iter() {
         if *interaction == Interaction::Clicked {
             match menu_button_action {
                 MenuButtonAction::Quit => app_exit_events.send(AppExit),
-                MenuButtonAction::Play => {
-                    game_state.set(GameState::Game).unwrap();
-                    menu_state.set(MenuState::Disabled).unwrap();
+                MenuButtonAction::Play => game_state.set(GameState::Game).unwrap(),
+                MenuButtonAction::Open(screen) => stack.0.push(screen.clone()),
+                MenuButtonAction::Back => {
+                    // the first screen has nowhere to go back to
+                    if stack.0.len() > 1 {
+                        stack.0.pop();
+                    }
+                }
+                MenuButtonAction::ResetControls => {
+                    settings.bindings = Default::default();
+                }
+                MenuButtonAction::Resume => {
+                    let _ = game_state.pop();
+                }
+                // replacing the whole state stack exits `Game`, despawning the run
+                MenuButtonAction::Restart => {
+                    let _ = game_state.replace(GameState::Game);
+                }
+                MenuButtonAction::QuitToMainMenu => {
+                    let _ = game_state.replace(GameState::Menu);
                 }
-                MenuButtonAction::Settings => menu_state.set(MenuState::Settings).unwrap(),
-                MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main).unwrap(),
             }
         }
     }
@@ -273,3 +637,91 @@ fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands
         commands.entity(entity).despawn_recursive();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::definition::{MenuItem, MENU_FILES};
+    use super::*;
+    use std::path::Path;
+
+    fn shipped_menus() -> Vec<(&'static str, MenuDefinition)> {
+        MENU_FILES
+            .iter()
+            .map(|file| {
+                let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("assets")
+                    .join(file);
+                let contents = std::fs::read_to_string(&path).unwrap();
+                let definition = ron::from_str(&contents)
+                    .unwrap_or_else(|e| panic!("{} does not parse: {}", file, e));
+                (*file, definition)
+            })
+            .collect()
+    }
+
+    // Translation keys and opened screens of the items
+    fn collect(items: &[MenuItem], keys: &mut Vec<String>, opened: &mut Vec<String>) {
+        for item in items {
+            match item {
+                MenuItem::Title(key) | MenuItem::Label(key) => keys.push(key.clone()),
+                MenuItem::Button { label, action } => {
+                    keys.push(label.clone());
+                    if let MenuButtonAction::Open(screen) = action {
+                        opened.push(screen.clone());
+                    }
+                }
+                MenuItem::Row(items) => collect(items, keys, opened),
+                MenuItem::Setting { label, values } => {
+                    keys.push(label.clone());
+                    keys.extend(
+                        values
+                            .iter()
+                            .filter_map(|v| v.label_key())
+                            .map(String::from),
+                    );
+                }
+                MenuItem::Bindings(actions) => {
+                    keys.extend(actions.iter().map(|a| action_key(*a).to_string()))
+                }
+                MenuItem::BindingMessage | MenuItem::Credits => {}
+            }
+        }
+    }
+
+    #[test]
+    fn shipped_menus_are_complete() {
+        let menus = shipped_menus();
+        assert!(MENU_FILES.contains(&"menus/main.menu.ron"));
+
+        let localization = Localization::default();
+        let mut opened = vec![
+            "main".to_string(),
+            "pause".to_string(),
+            "overrun".to_string(),
+        ];
+        for (file, definition) in menus.iter() {
+            let mut keys = Vec::new();
+            for screen in definition.screens.values() {
+                collect(&screen.items, &mut keys, &mut opened);
+            }
+            for key in keys {
+                for language in Language::ALL {
+                    assert!(
+                        localization.has(language, &key),
+                        "{} uses {:?}, which {:?} does not translate",
+                        file,
+                        key,
+                        language
+                    );
+                }
+            }
+        }
+        for screen in opened {
+            assert!(
+                menus.iter().any(|(_, d)| d.screens.contains_key(&screen)),
+                "no menu file has a screen named {:?}",
+                screen
+            );
+        }
+    }
+}
@@ -0,0 +1,365 @@
+pub mod storage;
+
+use super::actions::ActionMap;
+use super::locale::Language;
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const SETTINGS_KEY: &str = "settings";
+/// Layout version of the settings file. Bump it and add a branch to `migrate` when a key
+/// is renamed or changes meaning; new keys only need a default.
+const SETTINGS_VERSION: u32 = 1;
+
+/// Player facing options, edited from the settings menu.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Volumes in percent
+    pub master_volume: u32,
+    pub sfx_volume: u32,
+    pub music_volume: u32,
+    /// Multiplier applied to mouse movement when orbiting the camera
+    pub mouse_sensitivity: f32,
+    /// Multiplier applied to the speed of the keyboard camera keys
+    pub keyboard_camera_speed: f32,
+    pub invert_y: bool,
+    /// Seconds the camera takes to cover most of the way to where the input moved it, 0 follows
+    /// the input instantly
+    pub camera_smoothing: f32,
+    /// Keep orbiting for a moment after the orbit button is released
+    pub orbit_inertia: bool,
+    /// Show the far side of the whale in an inset in a corner of the HUD
+    pub rear_view: bool,
+    /// Width of the rear view inset in pixels, its height is three quarters of it
+    pub rear_view_size: u32,
+    pub msaa_samples: u32,
+    pub shadows: bool,
+    pub fullscreen: bool,
+    pub difficulty: Difficulty,
+    pub language: Language,
+    pub bindings: ActionMap,
+    /// Camera views stored with Ctrl+1..4, by path of the whale model they look at
+    pub viewpoints: BTreeMap<String, [Option<Viewpoint>; VIEWPOINT_SLOTS]>,
+}
+
+/// Number of camera views that can be stored per whale
+pub const VIEWPOINT_SLOTS: usize = 4;
+
+/// A stored camera view, the state of the game's `PanOrbitCamera`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Viewpoint {
+    pub focus: Vec3,
+    pub radius: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            master_volume: 100,
+            sfx_volume: 100,
+            music_volume: 100,
+            mouse_sensitivity: 1.0,
+            keyboard_camera_speed: 1.0,
+            invert_y: false,
+            camera_smoothing: 0.1,
+            orbit_inertia: true,
+            rear_view: false,
+            rear_view_size: 320,
+            msaa_samples: 4,
+            shadows: true,
+            fullscreen: false,
+            difficulty: Difficulty::default(),
+            language: Language::English,
+            bindings: ActionMap::default(),
+            viewpoints: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Brings values a hand edited or corrupted file may hold back into what the game supports,
+    /// falling back to the default where a value makes no sense at all
+    fn sanitized(mut self) -> Self {
+        let default = Settings::default();
+        fn clamp(value: f32, min: f32, max: f32, default: f32) -> f32 {
+            if value.is_finite() {
+                value.clamp(min, max)
+            } else {
+                default
+            }
+        }
+
+        self.master_volume = self.master_volume.min(100);
+        self.sfx_volume = self.sfx_volume.min(100);
+        self.music_volume = self.music_volume.min(100);
+        self.mouse_sensitivity = clamp(self.mouse_sensitivity, 0.1, 5.0, default.mouse_sensitivity);
+        self.keyboard_camera_speed = clamp(
+            self.keyboard_camera_speed,
+            0.1,
+            5.0,
+            default.keyboard_camera_speed,
+        );
+        self.camera_smoothing = clamp(self.camera_smoothing, 0.0, 1.0, default.camera_smoothing);
+        self.rear_view_size = self.rear_view_size.clamp(120, 1920);
+        // the renderer only supports these sample counts
+        self.msaa_samples = if self.msaa_samples <= 1 { 1 } else { 4 };
+        self
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Seconds between two barnacle spawns
+    pub fn spawn_interval(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.6,
+        }
+    }
+
+    /// Attached barnacles at which the whale is overrun and the run is lost
+    pub fn overrun_count(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 40,
+            Difficulty::Normal => 30,
+            Difficulty::Hard => 20,
+        }
+    }
+
+    /// Seconds a barnacle needs to attach itself to the whale
+    pub fn attach_seconds(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 6.0,
+            Difficulty::Normal => 5.0,
+            Difficulty::Hard => 4.0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SettingsHeader {
+    #[serde(default)]
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SettingsFile {
+    version: u32,
+    #[serde(default)]
+    settings: Settings,
+}
+
+/// Reads the persisted settings. Missing keys fall back to their default, and a missing or
+/// unreadable file falls back to `Settings::default()`.
+pub fn load() -> Settings {
+    // this runs before the app and its logger exist, so problems go to stderr
+    match storage::read(SETTINGS_KEY) {
+        Ok(contents) => parse(&contents).unwrap_or_else(|e| {
+            eprintln!("Ignoring invalid settings file: {}", e);
+            Settings::default()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Settings::default(),
+        Err(e) => {
+            eprintln!("Could not read settings: {}", e);
+            Settings::default()
+        }
+    }
+}
+
+/// Reads the settings from a file given on the command line. Unlike `load`, problems are
+/// returned since the player asked for that file explicitly.
+/// A missing file is created with the first change.
+pub fn load_file(path: &Path) -> Result<Settings, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => parse(&contents).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse(contents: &str) -> Result<Settings, ron::Error> {
+    let header: SettingsHeader = ron::from_str(contents)?;
+    let settings = if header.version < SETTINGS_VERSION {
+        migrate(header.version, contents)?
+    } else {
+        // files written by a newer version are read as far as this version understands them
+        ron::from_str::<SettingsFile>(contents)?.settings
+    };
+    Ok(settings.sanitized())
+}
+
+// Upgrades the contents of an older settings file to the current `Settings`
+fn migrate(version: u32, contents: &str) -> Result<Settings, ron::Error> {
+    match version {
+        // unversioned files hold the bare settings struct
+        0 => ron::from_str(contents),
+        _ => Ok(ron::from_str::<SettingsFile>(contents)?.settings),
+    }
+}
+
+fn save(settings: &Settings, path: Option<&Path>) {
+    let file = SettingsFile {
+        version: SETTINGS_VERSION,
+        settings: settings.clone(),
+    };
+    let result = ron::ser::to_string_pretty(&file, Default::default())
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            match path {
+                Some(path) => std::fs::write(path, contents),
+                None => storage::write(SETTINGS_KEY, &contents),
+            }
+            .map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        warn!("Could not save settings: {}", e);
+    }
+}
+
+/// File the settings were loaded from with `--config`, changes are saved back to it.
+/// `None` uses the default storage.
+#[derive(Default)]
+pub struct SettingsPath(pub Option<PathBuf>);
+
+/// Keeps the engine in sync with the `Settings` resource and persists every change.
+/// The resource itself is inserted in `main` before the app is built.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>()
+            .init_resource::<SettingsPath>()
+            .add_system(apply_settings)
+            .add_system(save_settings);
+    }
+}
+
+fn save_settings(settings: Res<Settings>, path: Res<SettingsPath>) {
+    if settings.is_changed() && !settings.is_added() {
+        save(&settings, path.0.as_deref());
+    }
+}
+
+// Pushes changed settings to the engine resources and entities they control
+fn apply_settings(
+    settings: Res<Settings>,
+    mut msaa: ResMut<Msaa>,
+    mut windows: ResMut<Windows>,
+    mut lights: Query<&mut PointLight>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    if msaa.samples != settings.msaa_samples {
+        msaa.samples = settings.msaa_samples;
+    }
+
+    if let Some(window) = windows.get_primary_mut() {
+        let mode = settings.window_mode();
+        if window.mode() != mode {
+            window.set_mode(mode);
+        }
+    }
+
+    for mut light in lights.iter_mut() {
+        light.shadows_enabled = settings.shadows;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_settings_read_back() {
+        let settings = Settings {
+            master_volume: 50,
+            mouse_sensitivity: 1.5,
+            msaa_samples: 1,
+            difficulty: Difficulty::Hard,
+            ..Default::default()
+        };
+        let file = SettingsFile {
+            version: SETTINGS_VERSION,
+            settings: settings.clone(),
+        };
+        let contents = ron::ser::to_string_pretty(&file, Default::default()).unwrap();
+        assert_eq!(parse(&contents).unwrap(), settings);
+    }
+
+    #[test]
+    fn missing_keys_keep_their_default() {
+        let settings = parse("(version: 1, settings: (sfx_volume: 25))").unwrap();
+        assert_eq!(settings.sfx_volume, 25);
+        assert_eq!(settings.music_volume, Settings::default().music_volume);
+        assert_eq!(parse("(version: 1)").unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn unversioned_files_are_migrated() {
+        let settings = parse("(master_volume: 75, invert_y: true, difficulty: Easy)").unwrap();
+        assert_eq!(settings.master_volume, 75);
+        assert!(settings.invert_y);
+        assert_eq!(settings.difficulty, Difficulty::Easy);
+        assert_eq!(parse("()").unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn newer_files_are_read_as_far_as_understood() {
+        let settings =
+            parse("(version: 99, settings: (music_volume: 0, hdr: true), extra: 1)").unwrap();
+        assert_eq!(settings.music_volume, 0);
+    }
+
+    #[test]
+    fn corrupt_files_are_rejected() {
+        assert!(parse("").is_err());
+        assert!(parse("(version: 1, settings: (").is_err());
+        assert!(parse("(version: \"one\")").is_err());
+        assert!(parse("(version: 1, settings: (msaa_samples: -4))").is_err());
+        assert!(parse("not settings at all").is_err());
+    }
+
+    #[test]
+    fn out_of_range_values_are_sanitized() {
+        let settings = parse(
+            "(version: 1, settings: (master_volume: 400, mouse_sensitivity: -2.0, \
+             keyboard_camera_speed: NaN, camera_smoothing: inf, msaa_samples: 3, \
+             rear_view_size: 0))",
+        )
+        .unwrap();
+        let default = Settings::default();
+        assert_eq!(settings.master_volume, 100);
+        assert_eq!(settings.mouse_sensitivity, 0.1);
+        assert_eq!(
+            settings.keyboard_camera_speed,
+            default.keyboard_camera_speed
+        );
+        assert_eq!(settings.camera_smoothing, default.camera_smoothing);
+        assert_eq!(settings.msaa_samples, 4);
+        assert_eq!(settings.rear_view_size, 120);
+        assert_eq!(parse("(msaa_samples: 0)").unwrap().msaa_samples, 1);
+    }
+}
@@ -0,0 +1,57 @@
+// Raw text storage for persisted data: one file per key in the user's config
+// directory on native targets, one localStorage entry per key in the browser.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod platform {
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    fn path(key: &str) -> io::Result<PathBuf> {
+        dirs::config_dir()
+            .map(|dir| dir.join("laughing-barnacle").join(format!("{}.ron", key)))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))
+    }
+
+    pub fn read(key: &str) -> io::Result<String> {
+        fs::read_to_string(path(key)?)
+    }
+
+    pub fn write(key: &str, contents: &str) -> io::Result<()> {
+        let path = path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    use std::io;
+
+    fn local_storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no localStorage"))
+    }
+
+    fn storage_key(key: &str) -> String {
+        format!("laughing-barnacle.{}", key)
+    }
+
+    pub fn read(key: &str) -> io::Result<String> {
+        local_storage()?
+            .get_item(&storage_key(key))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e)))?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, key.to_string()))
+    }
+
+    pub fn write(key: &str, contents: &str) -> io::Result<()> {
+        local_storage()?
+            .set_item(&storage_key(key), contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e)))
+    }
+}
+
+pub use platform::{read, write};